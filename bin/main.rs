@@ -5,7 +5,7 @@ use sudoku_rust::solution;
 
 impl Solution {
     fn solve_sudoku(data: &mut Vec<Vec<char>>) {
-        solution::Solution::solve_sudoku(data);
+        solution::Solution::solve_sudoku(data).unwrap();
     }
 }
 
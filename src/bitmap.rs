@@ -15,6 +15,7 @@ const MASK: Ty = variants::MASK as Ty;
 const GRID_SIZE_TY: usize =
     GRID_SIZE_BITS / (8*mem::size_of::<Ty>()) + ((GRID_SIZE_BITS % (8*mem::size_of::<Ty>()) > 0) as usize);
 
+#[derive(Copy, Clone)]
 pub struct Bitmap {
     data: [Ty; GRID_SIZE_TY],
 }
@@ -27,9 +28,11 @@ impl fmt::Debug for Bitmap {
 
 impl Bitmap {
     pub fn new() -> Bitmap {
-        Bitmap {
-            data: [Ty::MAX; GRID_SIZE_TY],
-        }
+        let mut data = [Ty::MAX; GRID_SIZE_TY];
+        // bits past the last cell must stay clear, otherwise count_ones never matches
+        data[GRID_SIZE_TY - 1] =
+            Ty::MAX >> (GRID_SIZE_TY * 8 * mem::size_of::<Ty>() - GRID_SIZE_BITS);
+        Bitmap { data }
     }
 
     pub fn all_variants_contain_single_digit(&self) -> bool {
@@ -82,6 +85,15 @@ impl Bitmap {
         self.get_variants(p).try_into()
     }
 
+    pub fn has_variant(&self, p: GridPoint, digit: Digit) -> bool {
+        let idx = Index::new(p, digit);
+        (self.data[idx.i()] >> idx.j()) & 1 == 1
+    }
+
+    pub fn remove_variant(&mut self, p: GridPoint, digit: Digit) {
+        self.clear_bit(Index::new(p, digit));
+    }
+
     fn clear_column(&mut self, x: GridColumn, d: Digit) {
         for y in grid::ROWS {
             self.clear_bit(Index::new(GridPoint { x, y }, d));
@@ -110,6 +122,45 @@ impl Bitmap {
         self.set_digit(p, digit);
     }
 
+    /// Places every cell whose variants collapsed to a single digit, repeating
+    /// until nothing changes. Fails as soon as some cell has no variants left.
+    pub fn propagate(&mut self) -> Result<(), &'static str> {
+        loop {
+            let before = self.count_ones();
+            for p in grid::POINTS {
+                let variants = self.get_variants(p);
+                match variants.count() {
+                    0 => return Err("no variants"),
+                    1 => self.set_known_digit(p, variants.try_into()?),
+                    _ => {}
+                }
+            }
+            if self.count_ones() == before {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Returns the unsolved cell with the fewest variants, or `None` when every
+    /// cell holds a single digit.
+    pub fn min_variants_point(&self) -> Option<GridPoint> {
+        let mut best: Option<(GridPoint, u32)> = None;
+        for p in grid::POINTS {
+            let count = self.get_variants(p).count();
+            if count < 2 {
+                continue;
+            }
+            match best {
+                Some((_, c)) if c <= count => {}
+                _ => best = Some((p, count)),
+            }
+            if count == 2 {
+                break;
+            }
+        }
+        best.map(|(p, _)| p)
+    }
+
     pub fn iter(&self) -> BitmapIterator {
         BitmapIterator {
             bitmap: self,
@@ -241,6 +292,17 @@ mod tests {
         assert_eq!(board, input, "\n input:\n{}", pretty(&input))
     }
 
+    #[test]
+    fn test_propagate_contradiction() {
+        let mut bitmap = Bitmap::new();
+        let a = GridPoint { x: GridColumn(0), y: GridRow(0) };
+        let b = GridPoint { x: GridColumn(5), y: GridRow(0) };
+        bitmap.set_digit(a, digit::FOUR);
+        bitmap.set_digit(b, digit::FOUR);
+
+        assert_eq!(bitmap.propagate(), Err("no variants"));
+    }
+
     #[test]
     fn test_row_iterator() {
         let mut bitmap = Bitmap::new();
@@ -0,0 +1,442 @@
+use crate::digit::Digit;
+use crate::grid;
+use crate::solution::for_each_point;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::sync::OnceLock;
+
+/// Cells as 0 for empty and 1 to 9 for the digits.
+type Cells = [[u8; grid::WIDTH]; grid::HEIGHT];
+
+/// Orders of three bands, stacks or lines.
+const ORDERS: [[usize; 3]; 6] = [
+    [0, 1, 2],
+    [0, 2, 1],
+    [1, 0, 2],
+    [1, 2, 0],
+    [2, 0, 1],
+    [2, 1, 0],
+];
+
+/// The part of a transform fixed while the top rows are being chosen: the
+/// columns, the rows placed so far and the digits seen in them.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Arrangement {
+    transposed: bool,
+    /// Index of the column order.
+    columns: u16,
+    /// Old rows already placed, as a bit set.
+    used: u16,
+    /// New label for each old digit, 0 while it has not been seen.
+    labels: [u8; 10],
+    next_label: u8,
+}
+
+impl Arrangement {
+    /// Whether old row `r` may come next. Bands are filled one at a time, so
+    /// a new band starts every third row.
+    fn allows(&self, row: usize, r: usize) -> bool {
+        let band = (self.used >> (r / grid::BLOCK_SIZE * grid::BLOCK_SIZE)) & 0b111;
+        self.used & (1 << r) == 0 && (band == 0) == row.is_multiple_of(grid::BLOCK_SIZE)
+    }
+}
+
+/// Old column for each new column, for every order that keeps the stacks
+/// together, numbered stack order first and then the order within each stack.
+fn column_orders() -> &'static [[u8; grid::WIDTH]] {
+    static TABLE: OnceLock<Vec<[u8; grid::WIDTH]>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut orders = Vec::with_capacity(6 * 6 * 6 * 6);
+        for stacks in ORDERS {
+            for first in ORDERS {
+                for second in ORDERS {
+                    for third in ORDERS {
+                        let mut columns = [0; grid::WIDTH];
+                        for (s, order) in [first, second, third].iter().enumerate() {
+                            columns[s * 3..s * 3 + 3].copy_from_slice(&lines(stacks[s], order));
+                        }
+                        orders.push(columns);
+                    }
+                }
+            }
+        }
+        orders
+    })
+}
+
+/// Finds canonical forms, keeping its buffers from one board to the next.
+/// Worth reusing when going through many boards.
+pub struct Canonicalizer {
+    current: Vec<Arrangement>,
+    next: Vec<Arrangement>,
+}
+
+impl Canonicalizer {
+    pub fn new() -> Canonicalizer {
+        Canonicalizer {
+            current: Vec::new(),
+            next: Vec::new(),
+        }
+    }
+
+    /// The minimal-lexicographic form of the board under the sudoku symmetry
+    /// group, as an 81 character line with '.' for empty cells. Boards that
+    /// are transforms of each other, puzzles or full grids, share the same
+    /// form.
+    ///
+    /// The two top rows are chosen together, then the other rows from the
+    /// top, keeping only the arrangements that tie for the smallest rows so
+    /// far. Digits are numbered in the order they first appear.
+    pub fn canonical(&mut self, board: &Vec<Vec<char>>) -> String {
+        let mut cells: Cells = [[0; grid::WIDTH]; grid::HEIGHT];
+        for_each_point(board, |p, &ch| {
+            if let Ok(d) = Digit::try_from(ch) {
+                cells[p.row().0][p.column().0] = usize::from(d) as u8 + 1;
+            }
+        });
+        let mut transposed = cells;
+        for (y, row) in cells.iter().enumerate() {
+            for (x, &d) in row.iter().enumerate() {
+                transposed[x][y] = d;
+            }
+        }
+        let grids = [cells, transposed];
+
+        let mut key = String::with_capacity(grid::WIDTH * grid::HEIGHT);
+        for row in 1..grid::HEIGHT {
+            let best = if row == 1 {
+                let (first, second) = self.top_rows(&grids);
+                push_row(&mut key, &first);
+                second
+            } else {
+                self.extend(&grids, row)
+            };
+            // arrangements reached through different row orders can meet
+            // again, which keeps boards with many empty rows from piling up
+            self.next.sort_unstable();
+            self.next.dedup();
+            std::mem::swap(&mut self.current, &mut self.next);
+            push_row(&mut key, &best);
+        }
+        key
+    }
+
+    /// Chooses the two top rows under every column order, a stack at a time
+    /// so that the orders sharing a losing start are dropped together. The
+    /// top row only depends on where its empty cells go, so it is known up
+    /// front and only the rows giving it are tried. Keeps the arrangements
+    /// giving the smallest second row in `next` and returns both rows.
+    fn top_rows(&mut self, grids: &[Cells; 2]) -> ([u8; grid::WIDTH], [u8; grid::WIDTH]) {
+        let first = grids
+            .iter()
+            .flat_map(|cells| cells.iter().map(smallest_first_row))
+            .min()
+            .unwrap();
+        let mut best = [u8::MAX; grid::WIDTH];
+        self.next.clear();
+        for (transposed, cells) in grids.iter().enumerate() {
+            for (r0, line) in cells.iter().enumerate() {
+                if smallest_first_row(line) != first {
+                    continue;
+                }
+                let band = r0 / grid::BLOCK_SIZE * grid::BLOCK_SIZE;
+                for r1 in (band..band + grid::BLOCK_SIZE).filter(|&r1| r1 != r0) {
+                    let top = Top::new([line, &cells[r1]]);
+                    for (s, stacks) in ORDERS.iter().enumerate() {
+                        for (i, first_order) in ORDERS.iter().enumerate() {
+                            let columns = lines(stacks[0], first_order);
+                            let Some(t) = top.fill(0, columns, &first, &best) else {
+                                continue;
+                            };
+                            for (j, second_order) in ORDERS.iter().enumerate() {
+                                let columns = lines(stacks[1], second_order);
+                                let Some(t) = t.fill(1, columns, &first, &best) else {
+                                    continue;
+                                };
+                                for (k, third_order) in ORDERS.iter().enumerate() {
+                                    let columns = lines(stacks[2], third_order);
+                                    let Some(t) = t.fill(2, columns, &first, &best) else {
+                                        continue;
+                                    };
+                                    let (second, labels, next_label) = t.second_row();
+                                    match second.cmp(&best) {
+                                        Ordering::Greater => continue,
+                                        Ordering::Less => {
+                                            best = second;
+                                            self.next.clear();
+                                        }
+                                        Ordering::Equal => {}
+                                    }
+                                    self.next.push(Arrangement {
+                                        transposed: transposed == 1,
+                                        columns: (((s * 6 + i) * 6 + j) * 6 + k) as u16,
+                                        used: 1 << r0 | 1 << r1,
+                                        labels,
+                                        next_label,
+                                    });
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        (first, best)
+    }
+
+    /// Tries every allowed row after every arrangement, keeps those giving
+    /// the smallest row in `next` and returns that row.
+    fn extend(&mut self, grids: &[Cells; 2], row: usize) -> [u8; grid::WIDTH] {
+        let mut best = [u8::MAX; grid::WIDTH];
+        self.next.clear();
+        for a in &self.current {
+            let columns = &column_orders()[a.columns as usize];
+            for (r, line) in grids[a.transposed as usize].iter().enumerate() {
+                if !a.allows(row, r) {
+                    continue;
+                }
+                let mut b = *a;
+                b.used |= 1 << r;
+                let mut values = [0; grid::WIDTH];
+                let mut order = Ordering::Equal;
+                for (i, &c) in columns.iter().enumerate() {
+                    let d = line[c as usize] as usize;
+                    if d != 0 && b.labels[d] == 0 {
+                        b.labels[d] = b.next_label;
+                        b.next_label += 1;
+                    }
+                    values[i] = b.labels[d];
+                    if order == Ordering::Equal {
+                        order = values[i].cmp(&best[i]);
+                        if order == Ordering::Greater {
+                            break;
+                        }
+                    }
+                }
+                match order {
+                    Ordering::Greater => continue,
+                    Ordering::Less => {
+                        best = values;
+                        self.next.clear();
+                    }
+                    Ordering::Equal => {}
+                }
+                self.next.push(b);
+            }
+        }
+        best
+    }
+}
+
+/// The two top rows being filled in from the left, one stack at a time. Only
+/// the first row is numbered as it goes, since its digits are numbered before
+/// any of the second row.
+#[derive(Copy, Clone)]
+struct Top<'a> {
+    lines: [&'a [u8; grid::WIDTH]; 2],
+    columns: [u8; grid::WIDTH],
+    labels: [u8; 10],
+    next_label: u8,
+}
+
+impl<'a> Top<'a> {
+    fn new(lines: [&'a [u8; grid::WIDTH]; 2]) -> Top<'a> {
+        Top {
+            lines,
+            columns: [0; grid::WIDTH],
+            labels: [0; 10],
+            next_label: 1,
+        }
+    }
+
+    /// Fills in a stack slot from the old columns, or gives up once the top
+    /// row leaves `first` or the second row is bound to come out larger than
+    /// `best`.
+    fn fill(
+        &self,
+        slot: usize,
+        columns: [u8; 3],
+        first: &[u8; grid::WIDTH],
+        best: &[u8; grid::WIDTH],
+    ) -> Option<Top<'a>> {
+        let mut next = *self;
+        let start = slot * grid::BLOCK_SIZE;
+        for (i, &c) in columns.iter().enumerate() {
+            let d = next.lines[0][c as usize] as usize;
+            if d != 0 && next.labels[d] == 0 {
+                next.labels[d] = next.next_label;
+                next.next_label += 1;
+            }
+            if next.labels[d] != first[start + i] {
+                return None;
+            }
+            next.columns[start + i] = c;
+        }
+        // a digit not numbered yet gets at least the next number, and the
+        // second row can only be as small as those lower bounds
+        let end = start + grid::BLOCK_SIZE;
+        let mut bound = [0; grid::WIDTH];
+        for (b, &c) in bound.iter_mut().zip(&next.columns[..end]) {
+            let d = next.lines[1][c as usize] as usize;
+            *b = match (d, next.labels[d]) {
+                (0, _) => 0,
+                (_, 0) => next.next_label,
+                (_, label) => label,
+            };
+        }
+        if bound[..end] > best[..end] {
+            None
+        } else {
+            Some(next)
+        }
+    }
+
+    /// The second row once every stack is filled in, with the numbering
+    /// after it.
+    fn second_row(&self) -> ([u8; grid::WIDTH], [u8; 10], u8) {
+        let mut labels = self.labels;
+        let mut next_label = self.next_label;
+        let mut values = [0; grid::WIDTH];
+        for (v, &c) in values.iter_mut().zip(&self.columns) {
+            let d = self.lines[1][c as usize] as usize;
+            if d != 0 && labels[d] == 0 {
+                labels[d] = next_label;
+                next_label += 1;
+            }
+            *v = labels[d];
+        }
+        (values, labels, next_label)
+    }
+}
+
+/// The smallest top row the line can give. Its digits are all different, so
+/// they are numbered from 1 in order wherever they go, and only the empty
+/// cells matter: they go first within each stack, and the stacks with the
+/// most of them go first.
+fn smallest_first_row(line: &[u8; grid::WIDTH]) -> [u8; grid::WIDTH] {
+    let mut empty = [0; grid::NUM_BLOCKS];
+    for (x, &d) in line.iter().enumerate() {
+        if d == 0 {
+            empty[x / grid::BLOCK_SIZE] += 1;
+        }
+    }
+    empty.sort_unstable_by(|a, b| b.cmp(a));
+    let mut row = [0; grid::WIDTH];
+    let mut label = 0;
+    for (s, &e) in empty.iter().enumerate() {
+        for value in &mut row[s * grid::BLOCK_SIZE + e..(s + 1) * grid::BLOCK_SIZE] {
+            label += 1;
+            *value = label;
+        }
+    }
+    row
+}
+
+fn push_row(key: &mut String, row: &[u8; grid::WIDTH]) {
+    key.extend(row.iter().map(|&v| match v {
+        0 => '.',
+        v => char::from(b'0' + v),
+    }));
+}
+
+/// The old columns of a stack, in the given order.
+fn lines(stack: usize, order: &[usize; 3]) -> [u8; 3] {
+    let mut columns = [0; 3];
+    for (i, &line) in order.iter().enumerate() {
+        columns[i] = (stack * grid::BLOCK_SIZE + line) as u8;
+    }
+    columns
+}
+
+impl Default for Canonicalizer {
+    fn default() -> Canonicalizer {
+        Canonicalizer::new()
+    }
+}
+
+/// The canonical form of a single board, see `Canonicalizer::canonical`. Going
+/// through many boards, a reused `Canonicalizer` saves allocating its buffers
+/// each time.
+pub fn canonical(board: &Vec<Vec<char>>) -> String {
+    Canonicalizer::new().canonical(board)
+}
+
+/// Whether one board can be turned into the other by the sudoku symmetry
+/// group.
+pub fn are_isomorphic(a: &Vec<Vec<char>>, b: &Vec<Vec<char>>) -> bool {
+    let mut canonicalizer = Canonicalizer::new();
+    canonicalizer.canonical(a) == canonicalizer.canonical(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canonical::{are_isomorphic, canonical, Canonicalizer};
+    use crate::generator::Random;
+    use crate::solution::parse;
+    use crate::transform::Transform;
+    use std::time::{Duration, Instant};
+
+    const PUZZLE: &str =
+        ".....1..74......39.21...4..94.......1..9...85.5..3....8...4....6....8..2....25.6.";
+    const SOLUTION: &str =
+        "539461827486257139721389456948512673163974285257836914872643591615798342394125768";
+    const EASY: &str =
+        ".1...364.9.36..1....5.9...3.5..3.8.....84.7..2...6...4..1..4.....852.91..9..1.45.";
+
+    #[test]
+    fn test_canonical() {
+        let key = canonical(&parse(SOLUTION));
+        assert_eq!(key.len(), 81);
+        assert!(key.starts_with("123456789"));
+        assert_eq!(canonical(&parse(&key)), key);
+
+        let key = canonical(&parse(PUZZLE));
+        assert_eq!(key.matches('.').count(), PUZZLE.matches('.').count());
+        assert!(key.starts_with('.'));
+        assert_eq!(canonical(&parse(&key)), key);
+        assert_eq!(canonical(&parse(&".".repeat(81))), ".".repeat(81));
+    }
+
+    #[test]
+    fn test_invariant_under_transforms() {
+        let mut random = Random::new(3);
+        let mut canonicalizer = Canonicalizer::new();
+        for line in [PUZZLE, SOLUTION, EASY] {
+            let board = parse(line);
+            let key = canonicalizer.canonical(&board);
+            for _ in 0..10 {
+                let moved = Transform::random(&mut random).apply_board(&board);
+                assert_eq!(canonicalizer.canonical(&moved), key);
+                assert!(are_isomorphic(&board, &moved));
+            }
+        }
+    }
+
+    #[test]
+    fn test_are_isomorphic() {
+        assert!(!are_isomorphic(&parse(PUZZLE), &parse(EASY)));
+        assert!(!are_isomorphic(&parse(PUZZLE), &parse(SOLUTION)));
+
+        // one clue more is a different puzzle
+        let mut board = parse(PUZZLE);
+        board[0][0] = '5';
+        assert!(!are_isomorphic(&parse(PUZZLE), &board));
+    }
+
+    #[test]
+    fn test_throughput() {
+        // full grids used to take milliseconds each, most of it spent on the
+        // ties left after the top row
+        let mut random = Random::new(4);
+        let mut canonicalizer = Canonicalizer::new();
+        let keys = [SOLUTION, PUZZLE].map(|line| canonicalizer.canonical(&parse(line)));
+        let start = Instant::now();
+        for _ in 0..100 {
+            let t = Transform::random(&mut random);
+            for (line, key) in [SOLUTION, PUZZLE].iter().zip(&keys) {
+                assert_eq!(&canonicalizer.canonical(&t.apply_board(&parse(line))), key);
+            }
+        }
+        assert!(start.elapsed() < Duration::from_secs(5), "took {:?}", start.elapsed());
+    }
+}
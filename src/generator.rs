@@ -0,0 +1,320 @@
+use crate::bitmap::Bitmap;
+use crate::digit::Digit;
+use crate::grid;
+use crate::grid::{BlockColumn, BlockPoint, BlockRow, GridBlock, GridPoint};
+use crate::rating::{rate, Band, Rating};
+use crate::solution::{has_unique_solution, write_bitmap, Solutions};
+use std::convert::TryFrom;
+use std::time::{Duration, Instant};
+
+/// SplitMix64: small and fast, and the same seed gives the same numbers on
+/// every platform.
+#[derive(Copy, Clone, Debug)]
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        Random { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// Which cells must keep or lose their clues together.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Symmetry {
+    None,
+    /// Unchanged by a half turn.
+    Rotational180,
+    /// Unchanged by a quarter turn.
+    Rotational90,
+    /// Mirrored in the main diagonal.
+    Diagonal,
+    /// Mirrored in the anti-diagonal.
+    AntiDiagonal,
+    /// Mirrored left to right.
+    Mirror,
+}
+
+impl Symmetry {
+    /// The point and every point the symmetry maps it to, the point first.
+    pub fn orbit(&self, p: GridPoint) -> Vec<GridPoint> {
+        let map: fn(&GridPoint) -> GridPoint = match self {
+            Symmetry::None => return vec![p],
+            Symmetry::Rotational180 => GridPoint::rotate_180,
+            Symmetry::Rotational90 => GridPoint::rotate_90,
+            Symmetry::Diagonal => GridPoint::transpose,
+            Symmetry::AntiDiagonal => GridPoint::anti_transpose,
+            Symmetry::Mirror => GridPoint::mirror,
+        };
+        let mut orbit = vec![p];
+        let mut next = map(&p);
+        while next != p {
+            orbit.push(next);
+            next = map(&next);
+        }
+        orbit
+    }
+}
+
+/// A puzzle along with the solution it was carved from.
+#[derive(Clone, Debug)]
+pub struct Puzzle {
+    pub board: Vec<Vec<char>>,
+    pub solution: Vec<Vec<char>>,
+}
+
+impl Puzzle {
+    pub fn clues(&self) -> usize {
+        self.board.iter().flatten().filter(|&&ch| ch != '.').count()
+    }
+}
+
+/// How long to keep generating before giving up. A limit left as `None` does
+/// not apply, and at least one must be set.
+#[derive(Copy, Clone, Debug)]
+pub struct Budget {
+    pub attempts: Option<usize>,
+    pub time: Option<Duration>,
+}
+
+impl Budget {
+    pub fn attempts(attempts: usize) -> Budget {
+        Budget {
+            attempts: Some(attempts),
+            time: None,
+        }
+    }
+
+    pub fn time(time: Duration) -> Budget {
+        Budget {
+            attempts: None,
+            time: Some(time),
+        }
+    }
+}
+
+/// Generates puzzles with a single solution. Each call to `generate` moves the
+/// random state on, so a seed fixes the whole sequence of puzzles.
+pub struct Generator {
+    random: Random,
+    /// Clues to stop at. Removal also stops when every clue left is needed,
+    /// so puzzles may keep more.
+    pub clues: usize,
+    /// Clues are removed a whole orbit at a time.
+    pub symmetry: Symmetry,
+}
+
+impl Generator {
+    pub fn new(seed: u64, clues: usize) -> Generator {
+        Generator {
+            random: Random::new(seed),
+            clues,
+            symmetry: Symmetry::None,
+        }
+    }
+
+    pub fn generate(&mut self) -> Puzzle {
+        let solution = self.solution();
+        let mut board = solution.clone();
+        let mut order: Vec<GridPoint> = grid::POINTS.into_iter().collect();
+        self.random.shuffle(&mut order);
+        let mut clues = grid::WIDTH * grid::HEIGHT;
+        for p in order {
+            let (y, x) = (p.row().0, p.column().0);
+            if board[y][x] == '.' {
+                continue;
+            }
+            let orbit = self.symmetry.orbit(p);
+            if clues < self.clues + orbit.len() {
+                continue;
+            }
+            for q in &orbit {
+                board[q.row().0][q.column().0] = '.';
+            }
+            if has_unique_solution(&board) {
+                clues -= orbit.len();
+            } else {
+                for q in &orbit {
+                    let (y, x) = (q.row().0, q.column().0);
+                    board[y][x] = solution[y][x];
+                }
+            }
+        }
+        Puzzle { board, solution }
+    }
+
+    /// Generates and rates puzzles until one falls in the band. Fails with
+    /// "budget exhausted" once the budget runs out, and with "unbounded budget"
+    /// when it sets no limit.
+    pub fn generate_rated(
+        &mut self,
+        band: Band,
+        budget: Budget,
+    ) -> Result<(Puzzle, Rating), &'static str> {
+        if budget.attempts.is_none() && budget.time.is_none() {
+            return Err("unbounded budget");
+        }
+        let start = Instant::now();
+        let mut attempts = 0;
+        loop {
+            if budget.attempts.is_some_and(|limit| attempts >= limit)
+                || budget.time.is_some_and(|limit| start.elapsed() >= limit)
+            {
+                return Err("budget exhausted");
+            }
+            attempts += 1;
+            let puzzle = self.generate();
+            let rating = rate(&puzzle.board)?;
+            if band.contains(&rating) {
+                return Ok((puzzle, rating));
+            }
+        }
+    }
+
+    /// A random complete grid. The diagonal blocks share no unit, so they are
+    /// filled with shuffled digits and the solver completes the rest.
+    fn solution(&mut self) -> Vec<Vec<char>> {
+        let mut bitmap = Bitmap::new();
+        for i in 0..grid::NUM_BLOCKS {
+            let block = GridBlock::from(i * grid::NUM_BLOCKS + i);
+            let mut digits: Vec<u8> = (0..grid::WIDTH as u8).collect();
+            self.random.shuffle(&mut digits);
+            for (j, &d) in digits.iter().enumerate() {
+                let point = block.grid_point(BlockPoint {
+                    x: BlockColumn(j % grid::BLOCK_SIZE),
+                    y: BlockRow(j / grid::BLOCK_SIZE),
+                });
+                bitmap.set_known_digit(point, Digit::try_from(d).unwrap());
+            }
+        }
+        let mut board = vec![vec!['.'; grid::WIDTH]; grid::HEIGHT];
+        write_bitmap(&bitmap, &mut board);
+        Solutions::new(&board).next().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generator::{Budget, Generator, Random, Symmetry};
+    use crate::grid;
+    use crate::rating::{Band, Tier};
+    use crate::solution::{has_unique_solution, Solutions};
+    use std::time::Duration;
+
+    #[test]
+    fn test_random() {
+        let mut a = Random::new(42);
+        let mut b = Random::new(42);
+        assert_eq!(a.next_u64(), b.next_u64());
+        let mut items: Vec<usize> = (0..20).collect();
+        a.shuffle(&mut items);
+        items.sort();
+        assert_eq!(items, (0..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_generate() {
+        let puzzle = Generator::new(7, 30).generate();
+        assert_eq!(puzzle.clues(), 30);
+        assert!(has_unique_solution(&puzzle.board));
+        assert_eq!(Solutions::new(&puzzle.board).next().unwrap(), puzzle.solution);
+
+        let again = Generator::new(7, 30).generate();
+        assert_eq!(again.board, puzzle.board);
+
+        let mut generator = Generator::new(7, 30);
+        generator.generate();
+        assert_ne!(generator.generate().board, puzzle.board);
+    }
+
+    #[test]
+    fn test_orbit() {
+        let p = grid::GridPoint::from(1);
+        assert_eq!(Symmetry::None.orbit(p).len(), 1);
+        assert_eq!(Symmetry::Rotational90.orbit(p).len(), 4);
+        assert_eq!(Symmetry::Rotational180.orbit(grid::GridPoint::from(40)).len(), 1);
+        assert_eq!(Symmetry::Diagonal.orbit(grid::GridPoint::from(10)).len(), 1);
+        assert_eq!(Symmetry::AntiDiagonal.orbit(grid::GridPoint::from(8)).len(), 1);
+        assert_eq!(Symmetry::Mirror.orbit(p), vec![p, grid::GridPoint::from(7)]);
+    }
+
+    #[test]
+    fn test_generate_symmetric() {
+        let symmetries = [
+            Symmetry::Rotational180,
+            Symmetry::Rotational90,
+            Symmetry::Diagonal,
+            Symmetry::AntiDiagonal,
+            Symmetry::Mirror,
+        ];
+        for symmetry in symmetries {
+            let mut generator = Generator::new(11, 26);
+            generator.symmetry = symmetry;
+            let puzzle = generator.generate();
+            assert!(has_unique_solution(&puzzle.board));
+            for p in grid::POINTS {
+                let given = |q: grid::GridPoint| puzzle.board[q.row().0][q.column().0] != '.';
+                assert!(symmetry.orbit(p).into_iter().all(|q| given(q) == given(p)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_generate_rated() {
+        let mut generator = Generator::new(5, 26);
+        let (puzzle, rating) = generator
+            .generate_rated(Band::Tier(Tier::Easy), Budget::attempts(20))
+            .unwrap();
+        assert_eq!(rating.tier, Tier::Easy);
+        assert!(has_unique_solution(&puzzle.board));
+
+        let (_, rating) = generator
+            .generate_rated(Band::Hardest("Naked Single"), Budget::attempts(50))
+            .unwrap();
+        assert_eq!(rating.hardest, Some("Naked Single"));
+
+        // the rater takes a hidden single over a cell left with one digit
+        let (_, rating) = generator
+            .generate_rated(Band::Score(1.0, 1.5), Budget::attempts(20))
+            .unwrap();
+        assert!(rating.solved);
+        assert_eq!(rating.hardest, Some("Hidden Single"));
+
+        let impossible = Band::Score(100.0, 200.0);
+        assert_eq!(
+            generator
+                .generate_rated(impossible, Budget::attempts(3))
+                .unwrap_err(),
+            "budget exhausted"
+        );
+        assert_eq!(
+            generator
+                .generate_rated(impossible, Budget::time(Duration::ZERO))
+                .unwrap_err(),
+            "budget exhausted"
+        );
+        let unbounded = Budget { attempts: None, time: None };
+        let error = generator.generate_rated(impossible, unbounded).unwrap_err();
+        assert_eq!(error, "unbounded budget");
+    }
+}
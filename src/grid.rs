@@ -1,6 +1,6 @@
 use crate::range::{Range, SameAs};
 use std::ops::Add;
-use std::{cmp, mem, ops};
+use std::{cmp, fmt, mem, ops};
 
 pub const WIDTH: usize = 9;
 pub const HEIGHT: usize = 9;
@@ -25,7 +25,29 @@ pub const BLOCK_ROWS: Range<BlockRow> = Range {
     end: BlockRow(NUM_BLOCKS),
 };
 
-#[derive(Copy, Clone)]
+pub const BLOCKS: Range<GridBlock> = Range {
+    start: GridBlock {
+        x: BlockColumn(0),
+        y: BlockRow(0),
+    },
+    end: GridBlock {
+        x: BlockColumn(0),
+        y: BlockRow(NUM_BLOCKS),
+    },
+};
+
+pub const POINTS: Range<GridPoint> = Range {
+    start: GridPoint {
+        x: GridColumn(0),
+        y: GridRow(0),
+    },
+    end: GridPoint {
+        x: GridColumn(0),
+        y: GridRow(HEIGHT),
+    },
+};
+
+#[derive(Copy, Clone, Debug)]
 pub struct GridColumn(pub usize);
 
 impl cmp::PartialEq for GridColumn {
@@ -62,7 +84,13 @@ impl From<usize> for GridColumn {
 
 impl SameAs<usize> for GridColumn {}
 
-#[derive(Copy, Clone)]
+impl fmt::Display for GridColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "column {}", self.0 + 1)
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
 pub struct GridRow(pub usize);
 
 impl cmp::PartialEq for GridRow {
@@ -99,12 +127,48 @@ impl From<usize> for GridRow {
 
 impl SameAs<usize> for GridRow {}
 
-#[derive(Copy, Clone)]
+impl fmt::Display for GridRow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "row {}", self.0 + 1)
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
 pub struct GridBlock {
     x: BlockColumn,
     y: BlockRow,
 }
 
+impl cmp::PartialEq for GridBlock {
+    fn eq(&self, other: &Self) -> bool {
+        self.x.0 == other.x.0 && self.y.0 == other.y.0
+    }
+}
+
+impl Into<usize> for GridBlock {
+    fn into(self) -> usize {
+        self.y.0 * NUM_BLOCKS + self.x.0
+    }
+}
+
+impl From<usize> for GridBlock {
+    fn from(u: usize) -> Self {
+        GridBlock {
+            x: BlockColumn(u % NUM_BLOCKS),
+            y: BlockRow(u / NUM_BLOCKS),
+        }
+    }
+}
+
+impl SameAs<usize> for GridBlock {}
+
+/// Blocks count from 1 at the top left, row by row.
+impl fmt::Display for GridBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "block {}", Into::<usize>::into(*self) + 1)
+    }
+}
+
 impl GridBlock {
     pub fn grid_point(&self, p: BlockPoint) -> GridPoint {
         GridPoint {
@@ -114,7 +178,7 @@ impl GridBlock {
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug)]
 pub struct BlockColumn(pub usize);
 
 impl Into<usize> for BlockColumn {
@@ -131,7 +195,7 @@ impl From<usize> for BlockColumn {
 
 impl SameAs<usize> for BlockColumn {}
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug)]
 pub struct BlockRow(pub usize);
 
 impl Into<usize> for BlockRow {
@@ -153,12 +217,18 @@ pub struct BlockPoint {
     pub y: BlockRow,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug)]
 pub struct GridPoint {
     pub x: GridColumn,
     pub y: GridRow,
 }
 
+impl cmp::PartialEq for GridPoint {
+    fn eq(&self, other: &Self) -> bool {
+        self.x == other.x && self.y == other.y
+    }
+}
+
 impl GridPoint {
     pub fn column(&self) -> GridColumn {
         self.x
@@ -172,4 +242,145 @@ impl GridPoint {
             y: BlockRow(self.y.0 / BLOCK_SIZE),
         }
     }
+    /// The 20 other cells sharing a row, a column or a block with this one.
+    pub fn peers(&self) -> Vec<GridPoint> {
+        POINTS.into_iter().filter(|p| self.sees(p)).collect()
+    }
+    /// Whether both points share a unit without being the same cell.
+    pub fn sees(&self, other: &GridPoint) -> bool {
+        self != other
+            && (self.x == other.x || self.y == other.y || self.block() == other.block())
+    }
+    /// Turned a quarter clockwise around the center cell.
+    pub fn rotate_90(&self) -> GridPoint {
+        GridPoint {
+            x: GridColumn(HEIGHT - 1 - self.y.0),
+            y: GridRow(self.x.0),
+        }
+    }
+    pub fn rotate_180(&self) -> GridPoint {
+        GridPoint {
+            x: GridColumn(WIDTH - 1 - self.x.0),
+            y: GridRow(HEIGHT - 1 - self.y.0),
+        }
+    }
+    /// Reflected in the main diagonal, from r1c1 to r9c9.
+    pub fn transpose(&self) -> GridPoint {
+        GridPoint {
+            x: GridColumn(self.y.0),
+            y: GridRow(self.x.0),
+        }
+    }
+    /// Reflected in the anti-diagonal, from r1c9 to r9c1.
+    pub fn anti_transpose(&self) -> GridPoint {
+        self.transpose().rotate_180()
+    }
+    /// Reflected left to right.
+    pub fn mirror(&self) -> GridPoint {
+        GridPoint {
+            x: GridColumn(WIDTH - 1 - self.x.0),
+            y: self.y,
+        }
+    }
+}
+
+impl Into<usize> for GridPoint {
+    fn into(self) -> usize {
+        self.y.0 * WIDTH + self.x.0
+    }
+}
+
+impl From<usize> for GridPoint {
+    fn from(u: usize) -> Self {
+        GridPoint {
+            x: GridColumn(u % WIDTH),
+            y: GridRow(u / WIDTH),
+        }
+    }
+}
+
+impl SameAs<usize> for GridPoint {}
+
+/// The usual `r3c5` notation, counting from 1.
+impl fmt::Display for GridPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "r{}c{}", self.y.0 + 1, self.x.0 + 1)
+    }
+}
+
+/// A row, a column or a block: nine cells that must hold every digit once.
+#[derive(Copy, Clone, Debug)]
+pub enum GridUnit {
+    Row(GridRow),
+    Column(GridColumn),
+    Block(GridBlock),
+}
+
+impl cmp::PartialEq for GridUnit {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (GridUnit::Row(a), GridUnit::Row(b)) => a == b,
+            (GridUnit::Column(a), GridUnit::Column(b)) => a == b,
+            (GridUnit::Block(a), GridUnit::Block(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for GridUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GridUnit::Row(y) => y.fmt(f),
+            GridUnit::Column(x) => x.fmt(f),
+            GridUnit::Block(b) => b.fmt(f),
+        }
+    }
+}
+
+impl GridUnit {
+    pub fn points(&self) -> [GridPoint; WIDTH] {
+        let mut points = [GridPoint::from(0); WIDTH];
+        for (i, point) in points.iter_mut().enumerate() {
+            *point = match *self {
+                GridUnit::Row(y) => GridPoint { x: GridColumn(i), y },
+                GridUnit::Column(x) => GridPoint { x, y: GridRow(i) },
+                GridUnit::Block(b) => b.grid_point(BlockPoint {
+                    x: BlockColumn(i % BLOCK_SIZE),
+                    y: BlockRow(i / BLOCK_SIZE),
+                }),
+            }
+        }
+        points
+    }
+
+    /// Cells shared by both units, in the order of `self`.
+    pub fn intersection(&self, other: &GridUnit) -> Vec<GridPoint> {
+        self.points()
+            .iter()
+            .copied()
+            .filter(|&p| other.contains(p))
+            .collect()
+    }
+
+    pub fn contains(&self, p: GridPoint) -> bool {
+        match *self {
+            GridUnit::Row(y) => p.row() == y,
+            GridUnit::Column(x) => p.column() == x,
+            GridUnit::Block(b) => p.block() == b,
+        }
+    }
+}
+
+/// All 27 units: rows first, then columns, then blocks.
+pub fn units() -> impl Iterator<Item = GridUnit> {
+    ROWS.into_iter()
+        .map(GridUnit::Row)
+        .chain(COLUMNS.into_iter().map(GridUnit::Column))
+        .chain(BLOCKS.into_iter().map(GridUnit::Block))
+}
+
+/// The cell in row `y` and column `x`, both counted from 0.
+#[cfg(test)]
+pub(crate) fn point(y: usize, x: usize) -> GridPoint {
+    GridPoint { x: GridColumn(x), y: GridRow(y) }
 }
@@ -0,0 +1,115 @@
+use crate::grid::GridUnit;
+use crate::strategy::Strategy;
+use crate::technique::Step;
+use std::fmt;
+
+/// How much of the next step to give away.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum HintLevel {
+    /// Only where to look.
+    Region,
+    /// Which technique to use.
+    Technique,
+    /// The whole step.
+    Answer,
+}
+
+#[derive(Clone, Debug)]
+pub enum Hint {
+    Region(Vec<GridUnit>),
+    Technique(&'static str),
+    Answer(Step),
+}
+
+impl fmt::Display for Hint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Hint::Region(units) => {
+                let units: Vec<String> = units.iter().map(|u| u.to_string()).collect();
+                write!(f, "Look at {}", units.join(" and "))
+            }
+            Hint::Technique(name) => write!(f, "Look for a {}", name),
+            Hint::Answer(step) => step.fmt(f),
+        }
+    }
+}
+
+/// The easiest deduction the standard strategy finds on the board, which is
+/// left as it is.
+pub fn hint(board: &Vec<Vec<char>>) -> Option<Step> {
+    Strategy::standard().first_step(board)
+}
+
+/// Part of the easiest deduction of the strategy, for players who want to
+/// find the rest themselves.
+pub fn hint_at(board: &Vec<Vec<char>>, strategy: &Strategy, level: HintLevel) -> Option<Hint> {
+    let step = strategy.first_step(board)?;
+    Some(match level {
+        HintLevel::Region => Hint::Region(region(&step)),
+        HintLevel::Technique => Hint::Technique(step.name),
+        HintLevel::Answer => Hint::Answer(step),
+    })
+}
+
+/// The units the step is built on or, for patterns spread over the grid, the
+/// blocks of its cells.
+fn region(step: &Step) -> Vec<GridUnit> {
+    if !step.units.is_empty() {
+        return step.units.clone();
+    }
+    let mut units = Vec::new();
+    for p in &step.cells {
+        let block = GridUnit::Block(p.block());
+        if !units.contains(&block) {
+            units.push(block);
+        }
+    }
+    units
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::grid::{GridPoint, GridUnit};
+    use crate::hint::{hint, hint_at, Hint, HintLevel};
+    use crate::solution::parse;
+    use crate::strategy::Strategy;
+
+    #[test]
+    fn test_hint() {
+        let line =
+            ".1...364.9.36..1....5.9...3.5..3.8.....84.7..2...6...4..1..4.....852.91..9..1.45.";
+        let board = parse(line);
+        let step = hint(&board).unwrap();
+        assert_eq!(step.placements.len(), 1);
+        assert_eq!(board, parse(line));
+
+        let solved = parse(
+            "812753649943682175675491283154237896369845721287169534521974368438526917796318452",
+        );
+        assert!(hint(&solved).is_none());
+        assert!(hint(&parse(&format!("11{}", ".".repeat(79)))).is_none());
+    }
+
+    #[test]
+    fn test_hint_levels() {
+        // only r1c9 is open
+        let board = parse(
+            "81275364.943682175675491283154237896369845721287169534521974368438526917796318452",
+        );
+        let strategy = Strategy::standard();
+        let region = hint_at(&board, &strategy, HintLevel::Region).unwrap();
+        match &region {
+            Hint::Region(units) => {
+                assert_eq!(units, &vec![GridUnit::Block(GridPoint::from(8).block())])
+            }
+            _ => panic!("expected a region"),
+        }
+        assert_eq!(region.to_string(), "Look at block 3");
+
+        let technique = hint_at(&board, &strategy, HintLevel::Technique).unwrap();
+        assert_eq!(technique.to_string(), "Look for a Naked Single");
+
+        let answer = hint_at(&board, &strategy, HintLevel::Answer).unwrap();
+        assert_eq!(answer.to_string(), "Naked Single: r1c9 can only be 9");
+    }
+}
@@ -1,6 +1,13 @@
 pub mod bitmap;
+pub mod canonical;
 pub mod digit;
+pub mod generator;
 pub mod grid;
+pub mod hint;
 pub mod range;
+pub mod rating;
 pub mod solution;
+pub mod strategy;
+pub mod technique;
+pub mod transform;
 pub mod variants;
@@ -0,0 +1,375 @@
+use crate::bitmap::Bitmap;
+use crate::grid::GridUnit;
+use crate::solution::has_unique_solution;
+use crate::strategy::{Progress, Strategy};
+use crate::technique::singles::{naked_single, HiddenSingle};
+use crate::technique::{digit_points, Candidate, Step};
+use std::fmt;
+
+/// Score of a puzzle the strategy cannot finish, above every technique it
+/// knows.
+pub const UNSOLVED_SCORE: f32 = 9.0;
+
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
+pub enum Tier {
+    /// Singles only.
+    Easy,
+    /// Locked candidates, pairs and X-Wings.
+    Medium,
+    /// Larger subsets and fish, wings, unique rectangles.
+    Hard,
+    /// Coloring and chains.
+    Expert,
+    /// ALS, forcing chains, or more than the strategy knows.
+    Extreme,
+}
+
+impl Tier {
+    pub fn from_score(score: f32) -> Tier {
+        if score <= 2.3 {
+            Tier::Easy
+        } else if score <= 3.4 {
+            Tier::Medium
+        } else if score <= 5.6 {
+            Tier::Hard
+        } else if score <= 7.2 {
+            Tier::Expert
+        } else {
+            Tier::Extreme
+        }
+    }
+}
+
+impl fmt::Display for Tier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Tier::Easy => "easy",
+            Tier::Medium => "medium",
+            Tier::Hard => "hard",
+            Tier::Expert => "expert",
+            Tier::Extreme => "extreme",
+        };
+        f.write_str(label)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Rating {
+    /// Difficulty of the hardest step, on a SudokuExplainer-like scale.
+    pub score: f32,
+    pub tier: Tier,
+    /// Name of the hardest step, `None` when nothing was left to deduce.
+    pub hardest: Option<&'static str>,
+    /// Whether the strategy finished the puzzle without guessing.
+    pub solved: bool,
+    pub steps: usize,
+}
+
+/// Renders as "7.1 expert (AIC)".
+impl fmt::Display for Rating {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.1} {}", self.score, self.tier)?;
+        if let Some(name) = self.hardest {
+            write!(f, " ({})", name)?;
+        }
+        Ok(())
+    }
+}
+
+/// A range of difficulty to look for.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Band {
+    /// Scores from the first to the second, both included.
+    Score(f32, f32),
+    Tier(Tier),
+    /// Needs this step and nothing harder, e.g. "X-Wing".
+    Hardest(&'static str),
+}
+
+impl Band {
+    pub fn contains(&self, rating: &Rating) -> bool {
+        match *self {
+            Band::Score(min, max) => min <= rating.score && rating.score <= max,
+            Band::Tier(tier) => rating.tier == tier,
+            Band::Hardest(name) => rating.solved && rating.hardest == Some(name),
+        }
+    }
+}
+
+/// Difficulty of a step of a built-in technique, following SudokuExplainer
+/// where it has the technique. Chains get harder with length.
+pub fn difficulty(step: &Step) -> Option<f32> {
+    let base = match step.name {
+        "Hidden Single" => match step.units.first() {
+            Some(GridUnit::Block(_)) => 1.2,
+            _ => 1.5,
+        },
+        "Naked Single" => 2.3,
+        "Pointing" => 2.6,
+        "Claiming" => 2.8,
+        "Naked Pair" => 3.0,
+        "X-Wing" => 3.2,
+        "Hidden Pair" => 3.4,
+        "Finned X-Wing" => 3.5,
+        "Sashimi X-Wing" | "Naked Triple" => 3.6,
+        "Swordfish" => 3.8,
+        "Hidden Triple" => 4.0,
+        "Finned Swordfish" => 4.1,
+        "Sashimi Swordfish" | "XY-Wing" => 4.2,
+        "XYZ-Wing" | "W-Wing" => 4.4,
+        "Unique Rectangle Type 1" | "Unique Rectangle Type 2" | "Unique Rectangle Type 4" => 4.5,
+        "Unique Rectangle Type 3" | "Unique Rectangle Type 5" | "Unique Rectangle Type 6" => 4.6,
+        "Hidden Rectangle" => 4.7,
+        "Naked Quad" => 5.0,
+        "Jellyfish" => 5.2,
+        "Hidden Quad" => 5.4,
+        "Finned Jellyfish" => 5.5,
+        "Sashimi Jellyfish" | "BUG+1" => 5.6,
+        "Color Trap" | "Color Wrap" => 6.5,
+        "Multi-Coloring" => 6.6,
+        "X-Chain" => 6.6 + length_bonus(step.candidates.len()),
+        "XY-Chain" => 6.7 + length_bonus(step.candidates.len()),
+        "AIC" | "Nice Loop" => 7.0 + length_bonus(step.candidates.len()),
+        "ALS-XZ" => 7.3,
+        "ALS-XY-Wing" => 7.5,
+        "Death Blossom" => 7.6,
+        "Nishio" => 7.8,
+        "Cell Forcing Chain" => 8.2,
+        "Unit Forcing Chain" => 8.4,
+        _ => return None,
+    };
+    Some(base)
+}
+
+/// SudokuExplainer adds 0.1 each time a chain passes 4, 6, 8, 12, 16, 24, ...
+/// candidates.
+fn length_bonus(length: usize) -> f32 {
+    let mut bonus = 0.0;
+    let mut limit = 4;
+    while length > limit {
+        bonus += 0.1;
+        limit = if limit.is_power_of_two() { limit / 2 * 3 } else { limit / 3 * 4 };
+    }
+    bonus
+}
+
+/// Lowest difficulty of any step the built-in technique can find, so the
+/// rater can pass over it once it has an easier step.
+fn floor(technique: &str) -> Option<f32> {
+    let floor = match technique {
+        "Hidden Single" => 1.2,
+        "Naked Single" => 2.3,
+        "Locked Candidates" => 2.6,
+        "Naked Pair" => 3.0,
+        "X-Wing" => 3.2,
+        "Hidden Pair" => 3.4,
+        "Finned X-Wing" => 3.5,
+        "Naked Triple" => 3.6,
+        "Swordfish" => 3.8,
+        "Hidden Triple" => 4.0,
+        "Finned Swordfish" => 4.1,
+        "XY-Wing" => 4.2,
+        "XYZ-Wing" | "W-Wing" => 4.4,
+        "Unique Rectangle" => 4.5,
+        "Naked Quad" => 5.0,
+        "Jellyfish" => 5.2,
+        "Hidden Quad" => 5.4,
+        "Finned Jellyfish" => 5.5,
+        "BUG+1" => 5.6,
+        "Simple Coloring" => 6.5,
+        "Multi-Coloring" | "X-Chain" => 6.6,
+        "XY-Chain" => 6.7,
+        "AIC" | "Nice Loop" => 7.0,
+        "ALS-XZ" => 7.3,
+        "ALS-XY-Wing" => 7.5,
+        "Death Blossom" => 7.6,
+        "Nishio" => 7.8,
+        "Cell Forcing Chain" => 8.2,
+        "Unit Forcing Chain" => 8.4,
+        _ => return None,
+    };
+    Some(floor)
+}
+
+/// Rates the puzzle with the uniqueness techniques enabled, as rating tools
+/// commonly do.
+pub fn rate(board: &Vec<Vec<char>>) -> Result<Rating, &'static str> {
+    rate_with(board, &Strategy::assuming_unique())
+}
+
+/// Solves the puzzle with the easiest step available each time and scores
+/// it by the hardest of them. The puzzle needs a single solution.
+pub fn rate_with(board: &Vec<Vec<char>>, strategy: &Strategy) -> Result<Rating, &'static str> {
+    if !has_unique_solution(board) {
+        return Err("no unique solution");
+    }
+    let mut progress = Progress::new(board)?;
+    let mut score = 0.0;
+    let mut hardest = None;
+    let mut steps = 0;
+    while let Some((step, d)) = easiest_step(&progress, strategy) {
+        progress.take(&step);
+        steps += 1;
+        if d > score {
+            score = d;
+            hardest = Some(step.name);
+        }
+    }
+    let solved = progress.solved();
+    if !solved {
+        score = UNSOLVED_SCORE.max(score);
+    }
+    Ok(Rating {
+        score,
+        tier: Tier::from_score(score),
+        hardest,
+        solved,
+        steps,
+    })
+}
+
+/// The easiest of the steps the strategy's techniques find, and of the cells
+/// already down to one digit. Such a cell counts as a hidden single when its
+/// digit fits nowhere else in a block or line. Techniques that cannot beat
+/// the easiest step so far are not asked.
+fn easiest_step(progress: &Progress, strategy: &Strategy) -> Option<(Step, f32)> {
+    let bitmap = progress.bitmap();
+    let mut best: Option<(Step, f32)> = None;
+    let beats = |best: &Option<(Step, f32)>, d: f32| best.as_ref().is_none_or(|b| d < b.1);
+    for candidate in progress.pending_singles() {
+        let step = single(bitmap, candidate);
+        let d = difficulty(&step).unwrap_or(UNSOLVED_SCORE);
+        if beats(&best, d) {
+            best = Some((step, d));
+        }
+    }
+    for technique in strategy.techniques() {
+        if !beats(&best, floor(technique.name()).unwrap_or(0.0)) {
+            continue;
+        }
+        let step = match technique.find(bitmap) {
+            Some(step) if step.apply(&mut bitmap.clone()) => step,
+            _ => continue,
+        };
+        let d = technique
+            .difficulty(&step)
+            .or_else(|| difficulty(&step))
+            .unwrap_or(UNSOLVED_SCORE);
+        if beats(&best, d) {
+            best = Some((step, d));
+        }
+    }
+    best
+}
+
+fn single(bitmap: &Bitmap, candidate: Candidate) -> Step {
+    let point = candidate.point;
+    let units = [
+        GridUnit::Block(point.block()),
+        GridUnit::Row(point.row()),
+        GridUnit::Column(point.column()),
+    ];
+    match units
+        .into_iter()
+        .find(|&unit| digit_points(bitmap, unit, candidate.digit) == [point])
+    {
+        Some(unit) => HiddenSingle { unit, candidate }.step(),
+        None => naked_single(candidate),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bitmap::Bitmap;
+    use crate::rating::{length_bonus, rate, rate_with, Tier, UNSOLVED_SCORE};
+    use crate::solution::parse;
+    use crate::strategy::Strategy;
+    use crate::technique::singles::HiddenSingles;
+    use crate::technique::{Step, Technique};
+
+    /// Hidden singles under a name the rating does not know, standing in for
+    /// a technique from another crate.
+    struct Renamed {
+        difficulty: Option<f32>,
+    }
+
+    impl Technique for Renamed {
+        fn name(&self) -> &'static str {
+            "Renamed"
+        }
+
+        fn find(&self, bitmap: &Bitmap) -> Option<Step> {
+            let step = HiddenSingles.find(bitmap)?;
+            Some(Step {
+                name: "Renamed",
+                ..step
+            })
+        }
+
+        fn difficulty(&self, _: &Step) -> Option<f32> {
+            self.difficulty
+        }
+    }
+
+    #[test]
+    fn test_length_bonus() {
+        assert_eq!(length_bonus(4), 0.0);
+        assert!((length_bonus(5) - 0.1).abs() < 1e-6);
+        assert!((length_bonus(8) - 0.2).abs() < 1e-6);
+        assert!((length_bonus(9) - 0.3).abs() < 1e-6);
+        assert!((length_bonus(13) - 0.4).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_rate() {
+        let easy = parse(
+            ".1...364.9.36..1....5.9...3.5..3.8.....84.7..2...6...4..1..4.....852.91..9..1.45.",
+        );
+        let rating = rate(&easy).unwrap();
+        assert!(rating.solved);
+        assert_eq!(rating.tier, Tier::Easy);
+        assert!(rating.to_string().starts_with(&format!("{:.1} easy", rating.score)));
+
+        let hard = parse(
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..",
+        );
+        let rating = rate(&hard).unwrap();
+        assert!(!rating.solved);
+        assert_eq!(rating.score, UNSOLVED_SCORE);
+        assert_eq!(rating.tier, Tier::Extreme);
+
+        assert_eq!(rate(&parse(&".".repeat(81))).unwrap_err(), "no unique solution");
+    }
+
+    #[test]
+    fn test_rate_easiest_step() {
+        // a cell down to one digit is rated as the hidden single it also is
+        let board = parse(
+            "..3.2.6..9..3.5..1..18.64....81.29..7.......8..67.82....26.95..8..2.3..9..5.1.3..",
+        );
+        let rating = rate(&board).unwrap();
+        assert!(rating.solved);
+        assert_eq!(rating.score, 1.2);
+        assert_eq!(rating.hardest, Some("Hidden Single"));
+    }
+
+    #[test]
+    fn test_rate_custom_technique() {
+        let board = parse(
+            ".1...364.9.36..1....5.9...3.5..3.8.....84.7..2...6...4..1..4.....852.91..9..1.45.",
+        );
+        let mut strategy = Strategy::new();
+        strategy.push(Renamed { difficulty: Some(2.0) });
+        let rating = rate_with(&board, &strategy).unwrap();
+        assert!(rating.solved);
+        assert_eq!(rating.score, 2.0);
+        assert_eq!(rating.hardest, Some("Renamed"));
+
+        let mut strategy = Strategy::new();
+        strategy.push(Renamed { difficulty: None });
+        let rating = rate_with(&board, &strategy).unwrap();
+        assert!(rating.solved);
+        assert_eq!(rating.score, UNSOLVED_SCORE);
+        assert_eq!(rating.hardest, Some("Renamed"));
+    }
+}
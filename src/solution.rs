@@ -1,7 +1,9 @@
 use crate::bitmap::Bitmap;
 use crate::digit::Digit;
+use crate::grid;
 use crate::grid::{GridBlock, GridColumn, GridPoint, GridRow};
 use crate::range::Range;
+use crate::strategy::Strategy;
 use std::cmp::Ordering;
 use std::convert::TryFrom;
 use std::iter::{
@@ -15,23 +17,161 @@ use std::{convert, iter, mem};
 pub struct Solution {}
 
 impl Solution {
-    pub fn solve_sudoku(board: &mut Vec<Vec<char>>) {
-        let mut bitmap = Box::new(Bitmap::new());
-        for_each_point(board, |point, ch| {
-            if let Ok(digit) = Digit::try_from(*ch) {
-                bitmap.set_known_digit(point, digit)
-            }
-        });
+    pub fn solve_sudoku(board: &mut Vec<Vec<char>>) -> Result<(), &'static str> {
+        Solution::solve_with(board, &Strategy::standard())
+    }
+
+    /// Solves with the given techniques first, guessing only where they stall.
+    pub fn solve_with(board: &mut Vec<Vec<char>>, strategy: &Strategy) -> Result<(), &'static str> {
+        let mut bitmap = to_bitmap(board);
+        strategy.deduce(&mut bitmap)?;
+        let bitmap = search(bitmap)?;
+        write_bitmap(&bitmap, board);
+        Ok(())
+    }
+}
 
-        while !bitmap.all_variants_contain_single_digit() {
+/// Builds a bitmap with every given digit of the board placed.
+pub fn to_bitmap(board: &Vec<Vec<char>>) -> Bitmap {
+    let mut bitmap = Bitmap::new();
+    for_each_point(board, |point, ch| {
+        if let Ok(digit) = Digit::try_from(*ch) {
+            bitmap.set_known_digit(point, digit)
+        }
+    });
+    bitmap
+}
+
+/// Writes solved cells of the bitmap into the board, `.` for the rest.
+pub fn write_bitmap(bitmap: &Bitmap, board: &mut Vec<Vec<char>>) {
+    for_each_point_mut(board, |point, ch| {
+        *ch = match bitmap.get_digit(point) {
+            Ok(d) => d.into(),
+            Err(_) => '.',
+        }
+    });
+}
 
+/// Counts the solutions of the board, stopping once `limit` of them are found.
+pub fn count_solutions(board: &Vec<Vec<char>>, limit: usize) -> usize {
+    let mut solutions = Solutions::new(board);
+    let mut total = 0;
+    while total < limit && solutions.next_bitmap().is_some() {
+        total += 1;
+    }
+    total
+}
+
+pub fn has_unique_solution(board: &Vec<Vec<char>>) -> bool {
+    count_solutions(board, 2) == 1
+}
+
+/// Whether the board has a single solution that every one of its clues is
+/// needed for.
+pub fn is_minimal(board: &Vec<Vec<char>>) -> bool {
+    if !has_unique_solution(board) {
+        return false;
+    }
+    let mut board = board.clone();
+    grid::POINTS.into_iter().all(|p| {
+        let (y, x) = (p.row().0, p.column().0);
+        let clue = mem::replace(&mut board[y][x], '.');
+        let needed = clue == '.' || !has_unique_solution(&board);
+        board[y][x] = clue;
+        needed
+    })
+}
+
+/// Removes the clues at `order`, in that order, whenever the solution stays
+/// unique. Clues removed earlier can make later ones needed, so the order
+/// decides which minimal puzzle comes out. With every point in `order` the
+/// result is minimal.
+pub fn minimize(
+    board: &Vec<Vec<char>>,
+    order: &[GridPoint],
+) -> Result<Vec<Vec<char>>, &'static str> {
+    if !has_unique_solution(board) {
+        return Err("no unique solution");
+    }
+    let mut board = board.clone();
+    for p in order {
+        let (y, x) = (p.row().0, p.column().0);
+        let clue = mem::replace(&mut board[y][x], '.');
+        if !has_unique_solution(&board) {
+            board[y][x] = clue;
         }
-        for_each_point_mut(board, |point, ch| {
-            *ch = match bitmap.get_digit(point) {
-                Ok(d) => d.into(),
-                Err(_) => '.',
+    }
+    Ok(board)
+}
+
+/// Runs the standard strategy until it stalls, leaving the rest to `search`.
+pub fn deduce(bitmap: &mut Bitmap) -> Result<(), &'static str> {
+    Strategy::standard().deduce(bitmap)
+}
+
+/// Like `deduce`, but also avoids deadly patterns. Only sound when the puzzle
+/// is known to have a single solution.
+pub fn deduce_assuming_unique(bitmap: &mut Bitmap) -> Result<(), &'static str> {
+    Strategy::assuming_unique().deduce(bitmap)
+}
+
+/// Depth-first search over a propagated bitmap. Branches on the cell with the
+/// fewest variants and backtracks when propagation hits a contradiction.
+pub fn search(bitmap: Bitmap) -> Result<Bitmap, &'static str> {
+    Solutions::from_bitmap(bitmap)
+        .next_bitmap()
+        .ok_or("no solution")
+}
+
+/// Lazily enumerates every solution of a board. Pending branches are kept as
+/// bitmap snapshots on a stack, so completions are produced one at a time in
+/// ascending digit order of the branching cells.
+pub struct Solutions {
+    stack: Vec<Bitmap>,
+}
+
+impl Solutions {
+    pub fn new(board: &Vec<Vec<char>>) -> Solutions {
+        let mut bitmap = to_bitmap(board);
+        match bitmap.propagate() {
+            Ok(()) => Solutions::from_bitmap(bitmap),
+            Err(_) => Solutions { stack: Vec::new() },
+        }
+    }
+
+    /// Starts the enumeration from an already propagated bitmap.
+    pub fn from_bitmap(bitmap: Bitmap) -> Solutions {
+        Solutions {
+            stack: vec![bitmap],
+        }
+    }
+
+    pub fn next_bitmap(&mut self) -> Option<Bitmap> {
+        while let Some(bitmap) = self.stack.pop() {
+            let point = match bitmap.min_variants_point() {
+                Some(p) => p,
+                None => return Some(bitmap),
+            };
+            for digit in bitmap.get_variants(point).into_iter().rev() {
+                let mut branch = bitmap;
+                branch.set_known_digit(point, digit);
+                if branch.propagate().is_ok() {
+                    self.stack.push(branch);
+                }
             }
-        });
+        }
+        None
+    }
+}
+
+impl Iterator for Solutions {
+    type Item = Vec<Vec<char>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bitmap = self.next_bitmap()?;
+        let mut board = vec![vec!['.'; grid::WIDTH]; grid::HEIGHT];
+        write_bitmap(&bitmap, &mut board);
+        Some(board)
     }
 }
 
@@ -61,6 +201,17 @@ pub fn for_each_point_mut(board: &mut Vec<Vec<char>>, f: impl Fn(GridPoint, &mut
     }
 }
 
+/// Reads a board from a single line of 81 characters, `.` or `0` marking an
+/// empty cell.
+pub fn parse(line: &str) -> Vec<Vec<char>> {
+    line.chars()
+        .map(|ch| if ch == '0' { '.' } else { ch })
+        .collect::<Vec<_>>()
+        .chunks(grid::WIDTH)
+        .map(|row| row.to_vec())
+        .collect()
+}
+
 pub fn pretty(input: &Vec<Vec<char>>) -> String {
     input
         .iter()
@@ -71,7 +222,12 @@ pub fn pretty(input: &Vec<Vec<char>>) -> String {
 
 #[cfg(test)]
 mod tests {
-    use crate::solution::{pretty, Solution};
+    use crate::grid;
+    use crate::grid::GridPoint;
+    use crate::solution::{
+        count_solutions, deduce, has_unique_solution, is_minimal, minimize, parse, pretty,
+        to_bitmap, Solution, Solutions,
+    };
     use std::fs;
     use std::fs::File;
 
@@ -83,11 +239,112 @@ mod tests {
         };
         let mut board: Vec<Vec<char>> = input.clone();
 
-        Solution::solve_sudoku(&mut board);
+        Solution::solve_sudoku(&mut board).unwrap();
 
         let out = File::open("output.txt").unwrap();
         let expected: Vec<Vec<char>> = serde_json::from_reader(out).unwrap();
 
         assert_eq!(board, expected, "\n input:\n{}", pretty(&input))
     }
+
+    #[test]
+    fn test_solve_hard() {
+        let input = parse(
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..",
+        );
+        let mut board = input.clone();
+
+        Solution::solve_sudoku(&mut board).unwrap();
+
+        let expected = parse(
+            "812753649943682175675491283154237896369845721287169534521974368438526917796318452",
+        );
+        assert_eq!(board, expected, "\n input:\n{}", pretty(&input))
+    }
+
+    #[test]
+    fn test_solve_contradiction() {
+        let mut board = parse(
+            "11...............................................................................",
+        );
+        assert_eq!(Solution::solve_sudoku(&mut board), Err("no variants"));
+    }
+
+    #[test]
+    fn test_count_solutions() {
+        let unique = parse(
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..",
+        );
+        assert_eq!(count_solutions(&unique, 10), 1);
+        assert!(has_unique_solution(&unique));
+
+        let empty = parse(&".".repeat(81));
+        assert_eq!(count_solutions(&empty, 10), 10);
+        assert!(!has_unique_solution(&empty));
+
+        let broken = parse(&format!("11{}", ".".repeat(79)));
+        assert_eq!(count_solutions(&broken, 10), 0);
+        assert!(!has_unique_solution(&broken));
+    }
+
+    #[test]
+    fn test_solutions_iterator() {
+        // the hard puzzle without its 4 has several hundred completions
+        let board = parse(
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9.......",
+        );
+        let solutions: Vec<_> = Solutions::new(&board).collect();
+        assert_eq!(solutions.len(), count_solutions(&board, 1000));
+        assert!(solutions.len() > 1);
+        let mut distinct = solutions.clone();
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(distinct.len(), solutions.len());
+        assert!(solutions.iter().all(has_unique_solution));
+
+        let empty = parse(&".".repeat(81));
+        assert_eq!(Solutions::new(&empty).take(1000).count(), 1000);
+    }
+
+    #[test]
+    fn test_deduce_without_guessing() {
+        // needs hidden singles, naked singles alone stall on it
+        let board = parse(
+            ".1...364.9.36..1....5.9...3.5..3.8.....84.7..2...6...4..1..4.....852.91..9..1.45.",
+        );
+        let mut bitmap = to_bitmap(&board);
+        bitmap.propagate().unwrap();
+        assert!(!bitmap.all_variants_contain_single_digit());
+
+        let mut bitmap = to_bitmap(&board);
+        deduce(&mut bitmap).unwrap();
+        assert!(bitmap.all_variants_contain_single_digit());
+    }
+
+    #[test]
+    fn test_minimize() {
+        let board = parse(
+            ".....1..74......39.21...4..94.......1..9...85.5..3....8...4....6....8..2....25.6.",
+        );
+        assert!(is_minimal(&board));
+        assert!(!is_minimal(&parse(&".".repeat(81))));
+
+        let solution = parse(
+            "539461827486257139721389456948512673163974285257836914872643591615798342394125768",
+        );
+        let mut padded = board.clone();
+        padded[0][0] = solution[0][0];
+        padded[4][4] = solution[4][4];
+        assert!(!is_minimal(&padded));
+
+        let forward: Vec<GridPoint> = grid::POINTS.into_iter().collect();
+        let backward: Vec<GridPoint> = forward.iter().rev().copied().collect();
+        for order in [forward, backward] {
+            let minimal = minimize(&padded, &order).unwrap();
+            assert!(is_minimal(&minimal));
+            assert_eq!(Solutions::new(&minimal).next().unwrap(), solution);
+        }
+        assert_eq!(minimize(&padded, &[]).unwrap(), padded);
+        assert!(minimize(&parse(&".".repeat(81)), &[]).is_err());
+    }
 }
@@ -0,0 +1,376 @@
+use crate::bitmap::Bitmap;
+use crate::digit::Digit;
+use crate::grid;
+use crate::solution::{for_each_point, to_bitmap};
+use crate::technique::als::{AlsKind, AlsTechnique};
+use crate::technique::chains::{ChainKind, Chains};
+use crate::technique::coloring::{MultiColoring, SimpleColoring};
+use crate::technique::fish::{BasicFish, FinnedFish};
+use crate::technique::forcing::{ForcingChains, ForcingKind};
+use crate::technique::intersections::LockedCandidates;
+use crate::technique::singles::{naked_single, HiddenSingles, NakedSingles};
+use crate::technique::subsets::{HiddenSubsets, NakedSubsets};
+use crate::technique::uniqueness::{BugPlusOne, UniqueRectangles};
+use crate::technique::wings::{WWing, XYWing, XYZWing};
+use crate::technique::{Candidate, Step, Technique};
+use std::convert::TryFrom;
+use std::fmt;
+
+/// An ordered list of techniques. Each step comes from the first technique
+/// in the list that finds one, so the order reads easiest first.
+pub struct Strategy {
+    techniques: Vec<Box<dyn Technique>>,
+}
+
+impl Strategy {
+    /// A strategy with no techniques, to be filled with `push`.
+    pub fn new() -> Strategy {
+        Strategy {
+            techniques: Vec::new(),
+        }
+    }
+
+    /// Every built-in technique that holds for puzzles with several
+    /// solutions, roughly from easiest to hardest.
+    pub fn standard() -> Strategy {
+        let mut strategy = Strategy::new();
+        strategy.push(HiddenSingles);
+        strategy.push(NakedSingles);
+        strategy.push(LockedCandidates);
+        strategy.push(NakedSubsets { size: 2 });
+        strategy.push(BasicFish { size: 2 });
+        strategy.push(HiddenSubsets { size: 2 });
+        strategy.push(NakedSubsets { size: 3 });
+        strategy.push(BasicFish { size: 3 });
+        strategy.push(HiddenSubsets { size: 3 });
+        strategy.push(XYWing);
+        strategy.push(XYZWing);
+        strategy.push(WWing);
+        strategy.push(NakedSubsets { size: 4 });
+        strategy.push(BasicFish { size: 4 });
+        strategy.push(HiddenSubsets { size: 4 });
+        strategy.push(FinnedFish { size: 2 });
+        strategy.push(FinnedFish { size: 3 });
+        strategy.push(FinnedFish { size: 4 });
+        strategy.push(SimpleColoring);
+        strategy.push(MultiColoring);
+        strategy.push(Chains::new(ChainKind::XChain));
+        strategy.push(Chains::new(ChainKind::XYChain));
+        strategy.push(Chains::new(ChainKind::AIC));
+        strategy.push(Chains::new(ChainKind::NiceLoop));
+        strategy.push(AlsTechnique { kind: AlsKind::XZ });
+        strategy.push(AlsTechnique {
+            kind: AlsKind::XYWing,
+        });
+        strategy.push(AlsTechnique {
+            kind: AlsKind::DeathBlossom,
+        });
+        strategy.push(ForcingChains {
+            kind: ForcingKind::Nishio,
+        });
+        strategy.push(ForcingChains {
+            kind: ForcingKind::Cell,
+        });
+        strategy.push(ForcingChains {
+            kind: ForcingKind::Unit,
+        });
+        strategy
+    }
+
+    /// The standard strategy plus the uniqueness techniques. Only sound when
+    /// the puzzle is known to have a single solution.
+    pub fn assuming_unique() -> Strategy {
+        let mut strategy = Strategy::standard();
+        let after_wings = strategy.position("W-Wing").unwrap() + 1;
+        strategy.insert(after_wings, UniqueRectangles);
+        let after_quads = strategy.position("Hidden Quad").unwrap() + 1;
+        strategy.insert(after_quads, BugPlusOne);
+        strategy
+    }
+
+    pub fn push(&mut self, technique: impl Technique + 'static) {
+        self.techniques.push(Box::new(technique));
+    }
+
+    pub fn insert(&mut self, index: usize, technique: impl Technique + 'static) {
+        self.techniques.insert(index, Box::new(technique));
+    }
+
+    /// Takes the technique with the given name out of the strategy.
+    pub fn remove(&mut self, name: &str) -> Option<Box<dyn Technique>> {
+        let index = self.position(name)?;
+        Some(self.techniques.remove(index))
+    }
+
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.techniques.iter().position(|t| t.name() == name)
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.techniques.iter().map(|t| t.name()).collect()
+    }
+
+    pub(crate) fn techniques(&self) -> impl Iterator<Item = &dyn Technique> {
+        self.techniques.iter().map(|t| t.as_ref())
+    }
+
+    /// The step of the first technique that finds one changing the bitmap.
+    pub fn next_step(&self, bitmap: &Bitmap) -> Option<Step> {
+        self.techniques
+            .iter()
+            .filter_map(|t| t.find(bitmap))
+            .find(|step| step.apply(&mut bitmap.clone()))
+    }
+
+    /// Solves the board one step at a time and records every step. Unlike
+    /// `deduce` it does not propagate between steps: a cell left with a single
+    /// digit is logged as a naked single before the strategy is asked for the
+    /// next step.
+    pub fn solve_log(&self, board: &Vec<Vec<char>>) -> Result<SolveLog, &'static str> {
+        let mut progress = Progress::new(board)?;
+        let mut steps = Vec::new();
+        while let Some(step) = progress.next(self) {
+            steps.push(step);
+        }
+        Ok(SolveLog {
+            steps,
+            solved: progress.solved(),
+        })
+    }
+
+    /// The first step `solve_log` would take on the board, or `None` when the
+    /// strategy is stuck or the board is broken.
+    pub fn first_step(&self, board: &Vec<Vec<char>>) -> Option<Step> {
+        Progress::new(board).ok()?.next(self)
+    }
+
+    /// Alternates propagation with the strategy's steps until neither of them
+    /// makes progress, leaving the rest to `search`.
+    pub fn deduce(&self, bitmap: &mut Bitmap) -> Result<(), &'static str> {
+        loop {
+            bitmap.propagate()?;
+            match self.next_step(bitmap) {
+                Some(step) if step.apply(bitmap) => continue,
+                _ => return Ok(()),
+            }
+        }
+    }
+}
+
+/// A board being solved step by step: the candidates left, and which cells
+/// are filled in, given or logged.
+pub(crate) struct Progress {
+    bitmap: Bitmap,
+    known: Vec<bool>,
+}
+
+impl Progress {
+    pub(crate) fn new(board: &Vec<Vec<char>>) -> Result<Progress, &'static str> {
+        let bitmap = to_bitmap(board);
+        let mut check = bitmap;
+        check.propagate()?;
+        let mut known = vec![false; grid::WIDTH * grid::HEIGHT];
+        for_each_point(board, |point, &ch| {
+            known[Into::<usize>::into(point)] = Digit::try_from(ch).is_ok();
+        });
+        Ok(Progress { bitmap, known })
+    }
+
+    pub(crate) fn bitmap(&self) -> &Bitmap {
+        &self.bitmap
+    }
+
+    pub(crate) fn solved(&self) -> bool {
+        self.bitmap.all_variants_contain_single_digit()
+    }
+
+    /// Takes the next step. A cell already down to one digit comes before
+    /// anything the strategy finds.
+    fn next(&mut self, strategy: &Strategy) -> Option<Step> {
+        let step = match self.pending_singles().first() {
+            Some(&candidate) => naked_single(candidate),
+            None => strategy.next_step(&self.bitmap)?,
+        };
+        self.take(&step);
+        Some(step)
+    }
+
+    /// Applies the step and counts the cells it places as logged.
+    pub(crate) fn take(&mut self, step: &Step) {
+        step.apply(&mut self.bitmap);
+        for c in &step.placements {
+            self.known[Into::<usize>::into(c.point)] = true;
+        }
+    }
+
+    /// Cells down to one digit that no logged step has placed yet.
+    pub(crate) fn pending_singles(&self) -> Vec<Candidate> {
+        grid::POINTS
+            .into_iter()
+            .filter(|&p| !self.known[Into::<usize>::into(p)])
+            .filter_map(|point| {
+                let variants = self.bitmap.get_variants(point);
+                let digit = variants.into_iter().next()?;
+                (variants.count() == 1).then_some(Candidate { point, digit })
+            })
+            .collect()
+    }
+}
+
+/// The steps of a solve, in the order they were taken.
+pub struct SolveLog {
+    pub steps: Vec<Step>,
+    /// Whether the steps reach the solution, or the strategy ran out first.
+    pub solved: bool,
+}
+
+/// One numbered line per step.
+impl fmt::Display for SolveLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, step) in self.steps.iter().enumerate() {
+            writeln!(f, "{}. {}", i + 1, step)?;
+        }
+        if !self.solved {
+            writeln!(f, "No technique applies, the rest needs guessing.")?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Strategy {
+    fn default() -> Strategy {
+        Strategy::standard()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bitmap::Bitmap;
+    use crate::grid;
+    use crate::solution::{parse, to_bitmap};
+    use crate::strategy::Strategy;
+    use crate::technique::singles::{HiddenSingles, NakedSingles};
+    use crate::technique::{Step, Technique};
+
+    /// Gives up right away, standing in for a technique from another crate.
+    struct Nothing;
+
+    impl Technique for Nothing {
+        fn name(&self) -> &'static str {
+            "Nothing"
+        }
+
+        fn find(&self, _: &Bitmap) -> Option<Step> {
+            None
+        }
+    }
+
+    /// Finds a step that changes nothing, like a technique with a stale cache.
+    struct Stale;
+
+    impl Technique for Stale {
+        fn name(&self) -> &'static str {
+            "Stale"
+        }
+
+        fn find(&self, _: &Bitmap) -> Option<Step> {
+            Some(Step {
+                name: "Stale",
+                placements: Vec::new(),
+                eliminations: Vec::new(),
+                units: Vec::new(),
+                cells: Vec::new(),
+                candidates: Vec::new(),
+                explanation: String::new(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_configure() {
+        let standard = Strategy::standard();
+        assert!(standard.position("Unique Rectangle").is_none());
+        let unique = Strategy::assuming_unique();
+        assert_eq!(unique.names().len(), standard.names().len() + 2);
+        assert!(unique.position("BUG+1").is_some());
+
+        let mut strategy = Strategy::new();
+        strategy.push(NakedSingles);
+        strategy.insert(0, Nothing);
+        strategy.push(HiddenSingles);
+        assert_eq!(strategy.names(), vec!["Nothing", "Naked Single", "Hidden Single"]);
+        assert!(strategy.remove("Naked Single").is_some());
+        assert!(strategy.remove("Naked Single").is_none());
+        assert_eq!(strategy.names(), vec!["Nothing", "Hidden Single"]);
+    }
+
+    #[test]
+    fn test_solve_log() {
+        let board = parse(
+            ".1...364.9.36..1....5.9...3.5..3.8.....84.7..2...6...4..1..4.....852.91..9..1.45.",
+        );
+        let empty = board.iter().flatten().filter(|&&ch| ch == '.').count();
+        let log = Strategy::standard().solve_log(&board).unwrap();
+        assert!(log.solved);
+        assert_eq!(log.steps.len(), empty);
+        assert!(log.steps.iter().all(|s| s.placements.len() == 1));
+
+        let text = log.to_string();
+        assert_eq!(text.lines().count(), empty);
+        assert!(text.starts_with("1. "));
+        assert!(text.contains("Hidden Single: "));
+        assert!(text.contains("Naked Single: "));
+    }
+
+    #[test]
+    fn test_solve_log_with_eliminations() {
+        // stalls on singles until some candidates are eliminated
+        let board = parse(
+            ".........9.46.7....768.41..3.97.1.8...8...3...5.3.87.2..75.261....4.32.8.........",
+        );
+        let log = Strategy::standard().solve_log(&board).unwrap();
+        assert!(log.solved);
+        assert!(log.steps.iter().any(|s| s.placements.is_empty()));
+
+        let mut placed = [false; grid::WIDTH * grid::HEIGHT];
+        for c in log.steps.iter().flat_map(|s| &s.placements) {
+            let index: usize = c.point.into();
+            assert!(!placed[index], "{} placed twice", c.point);
+            placed[index] = true;
+        }
+    }
+
+    #[test]
+    fn test_deduce_with_singles_only() {
+        // needs hidden singles, naked singles alone stall on it
+        let board = parse(
+            ".1...364.9.36..1....5.9...3.5..3.8.....84.7..2...6...4..1..4.....852.91..9..1.45.",
+        );
+        let mut naked = Strategy::new();
+        naked.push(NakedSingles);
+        let mut bitmap = to_bitmap(&board);
+        naked.deduce(&mut bitmap).unwrap();
+        assert!(!bitmap.all_variants_contain_single_digit());
+
+        naked.push(HiddenSingles);
+        let mut bitmap = to_bitmap(&board);
+        naked.deduce(&mut bitmap).unwrap();
+        assert!(bitmap.all_variants_contain_single_digit());
+    }
+
+    #[test]
+    fn test_deduce_past_stale_step() {
+        let board = parse(
+            ".1...364.9.36..1....5.9...3.5..3.8.....84.7..2...6...4..1..4.....852.91..9..1.45.",
+        );
+        let mut strategy = Strategy::new();
+        strategy.push(Stale);
+        strategy.push(HiddenSingles);
+        strategy.push(NakedSingles);
+        let mut bitmap = to_bitmap(&board);
+        bitmap.propagate().unwrap();
+        assert_eq!(strategy.next_step(&bitmap).unwrap().name, "Hidden Single");
+        strategy.deduce(&mut bitmap).unwrap();
+        assert!(bitmap.all_variants_contain_single_digit());
+    }
+}
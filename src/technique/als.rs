@@ -0,0 +1,484 @@
+use crate::bitmap::Bitmap;
+use crate::digit::Digit;
+use crate::grid;
+use crate::grid::{GridPoint, GridUnit};
+use crate::technique::{
+    candidates_in, combinations, common_peer_eliminations, digits, is_solved, list, outcome,
+    Candidate, Step, Technique,
+};
+use crate::variants;
+use crate::variants::Variants;
+
+/// Largest almost locked set the search considers.
+pub const MAX_SIZE: usize = 5;
+
+/// Almost locked set: `points.len()` cells of a unit holding one digit more
+/// than there are cells.
+#[derive(Clone, Debug)]
+pub struct Als {
+    pub unit: GridUnit,
+    pub points: Vec<GridPoint>,
+    pub digits: Variants,
+}
+
+impl Als {
+    fn digit_points(&self, bitmap: &Bitmap, digit: Digit) -> Vec<GridPoint> {
+        self.points
+            .iter()
+            .copied()
+            .filter(|&p| bitmap.has_variant(p, digit))
+            .collect()
+    }
+
+    fn overlaps(&self, other: &Als) -> bool {
+        self.points.iter().any(|p| other.points.contains(p))
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AlsKind {
+    XZ,
+    XYWing,
+    DeathBlossom,
+}
+
+/// `digit` must go in one of the `sets`, so cells seeing all its cells there
+/// lose it. `restricted` holds the restricted common digits linking the
+/// sets, one per petal for a Death Blossom. With two of them, a digit other
+/// than those goes in each set holding it, so seeing its cells in one set is
+/// enough.
+#[derive(Clone, Debug)]
+pub struct AlsDeduction {
+    pub kind: AlsKind,
+    pub sets: Vec<Als>,
+    pub stem: Option<GridPoint>,
+    pub restricted: Vec<Digit>,
+    pub digit: Digit,
+    pub eliminations: Vec<Candidate>,
+}
+
+impl AlsDeduction {
+    pub fn name(&self) -> &'static str {
+        als_name(self.kind)
+    }
+
+    pub fn step(&self, bitmap: &Bitmap) -> Step {
+        let mut cells: Vec<GridPoint> = self.stem.into_iter().collect();
+        for set in &self.sets {
+            cells.extend(set.points.iter().filter(|p| !cells.contains(p)).copied().collect::<Vec<_>>());
+        }
+        Step {
+            name: self.name(),
+            placements: Vec::new(),
+            eliminations: self.eliminations.clone(),
+            units: self.sets.iter().map(|set| set.unit).collect(),
+            candidates: candidates_in(bitmap, &cells, variants::ANY),
+            cells,
+            explanation: self.explanation(),
+        }
+    }
+
+    fn explanation(&self) -> String {
+        let sets: Vec<String> = self
+            .sets
+            .iter()
+            .map(|set| format!("{} ({})", list(set.points.iter()), digits(set.digits)))
+            .collect();
+        let restricted = list(self.restricted.iter().map(|&d| char::from(d)));
+        let pattern = match self.stem {
+            Some(stem) => format!(
+                "each of {}'s candidates {} sees its set among {}",
+                stem,
+                restricted,
+                sets.join("; ")
+            ),
+            None if self.kind == AlsKind::XZ && self.restricted.len() == 2 => format!(
+                "the almost locked sets {} are linked by {} and both locked",
+                sets.join("; "),
+                restricted
+            ),
+            None => format!(
+                "the almost locked sets {} are linked by {}",
+                sets.join("; "),
+                restricted
+            ),
+        };
+        format!(
+            "{}, so {} is in one of them and {}",
+            pattern,
+            char::from(self.digit),
+            outcome(&[], &self.eliminations)
+        )
+    }
+}
+
+fn als_name(kind: AlsKind) -> &'static str {
+    match kind {
+        AlsKind::XZ => "ALS-XZ",
+        AlsKind::XYWing => "ALS-XY-Wing",
+        AlsKind::DeathBlossom => "Death Blossom",
+    }
+}
+
+/// Every almost locked set of up to `MAX_SIZE` cells, each cell set once even
+/// when it lies in several units.
+pub fn almost_locked_sets(bitmap: &Bitmap) -> Vec<Als> {
+    let mut result: Vec<Als> = Vec::new();
+    for unit in grid::units() {
+        let open: Vec<GridPoint> = unit
+            .points()
+            .iter()
+            .copied()
+            .filter(|&p| !is_solved(bitmap.get_variants(p)))
+            .collect();
+        for size in 1..=MAX_SIZE.min(open.len()) {
+            for picked in combinations(open.len(), size) {
+                let points: Vec<GridPoint> = picked.iter().map(|&i| open[i]).collect();
+                let digits = points
+                    .iter()
+                    .fold(Variants::new(), |acc, &p| acc | bitmap.get_variants(p));
+                if digits.count() as usize != size + 1 {
+                    continue;
+                }
+                let known = result.iter().any(|als| {
+                    als.points.len() == size && points.iter().all(|p| als.points.contains(p))
+                });
+                if !known {
+                    result.push(Als {
+                        unit,
+                        points,
+                        digits,
+                    });
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Digits common to both sets whose cells in one set all see its cells in
+/// the other: at most one of the sets can hold them.
+fn restricted_commons(bitmap: &Bitmap, a: &Als, b: &Als) -> Vec<Digit> {
+    let common = a.digits & b.digits;
+    if common.count() == 0 || a.overlaps(b) {
+        return Vec::new();
+    }
+    common
+        .into_iter()
+        .filter(|&digit| {
+            let (xa, xb) = (a.digit_points(bitmap, digit), b.digit_points(bitmap, digit));
+            xa.iter().all(|p| xb.iter().all(|q| p.sees(q)))
+        })
+        .collect()
+}
+
+/// Every pair of sets with restricted commons, as indices into `sets` in
+/// increasing order. Each pair is checked once, which is most of the work
+/// of the ALS searches.
+fn linked_pairs(bitmap: &Bitmap, sets: &[Als]) -> Vec<(usize, usize, Vec<Digit>)> {
+    combinations(sets.len(), 2)
+        .into_iter()
+        .map(|picked| {
+            let (a, b) = (picked[0], picked[1]);
+            (a, b, restricted_commons(bitmap, &sets[a], &sets[b]))
+        })
+        .filter(|(_, _, restricted)| !restricted.is_empty())
+        .collect()
+}
+
+/// Eliminations of a digit from cells seeing all its cells in the sets.
+fn eliminations(bitmap: &Bitmap, digit: Digit, sets: &[&Als]) -> Vec<Candidate> {
+    let points: Vec<GridPoint> = sets
+        .iter()
+        .flat_map(|als| als.digit_points(bitmap, digit))
+        .collect();
+    common_peer_eliminations(bitmap, digit, &points)
+}
+
+/// Two sets joined by a restricted common `x`: at least one of them is
+/// locked without `x`, so a common digit `z` goes in one of them. Joined by
+/// two restricted commons, both sets are locked: each restricted common goes
+/// in one of them, and every other digit of a set goes in that set.
+pub fn als_xz(bitmap: &Bitmap) -> Vec<AlsDeduction> {
+    let sets = almost_locked_sets(bitmap);
+    let mut result = Vec::new();
+    for (a, b, restricted) in linked_pairs(bitmap, &sets) {
+        let (a, b) = (&sets[a], &sets[b]);
+        let mut push = |digit: Digit, eliminations: Vec<Candidate>| {
+            if !eliminations.is_empty() {
+                result.push(AlsDeduction {
+                    kind: AlsKind::XZ,
+                    sets: vec![a.clone(), b.clone()],
+                    stem: None,
+                    restricted: restricted.clone(),
+                    digit,
+                    eliminations,
+                });
+            }
+        };
+        match restricted[..] {
+            [x] => {
+                for z in a.digits & b.digits ^ x {
+                    push(z, eliminations(bitmap, z, &[a, b]));
+                }
+            }
+            [x, y] => {
+                for digit in [x, y] {
+                    push(digit, eliminations(bitmap, digit, &[a, b]));
+                }
+                for set in [a, b] {
+                    for z in set.digits ^ x ^ y {
+                        push(z, eliminations(bitmap, z, &[set]));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    result
+}
+
+/// Sets `a` and `b` each linked to a pivot set `c` by different restricted
+/// commons: `c` cannot lose both, so `a` or `b` is locked and holds `z`.
+pub fn als_xy_wings(bitmap: &Bitmap) -> Vec<AlsDeduction> {
+    let sets = almost_locked_sets(bitmap);
+    let mut links: Vec<Vec<(usize, Digit)>> = vec![Vec::new(); sets.len()];
+    for (a, b, restricted) in linked_pairs(bitmap, &sets) {
+        for d in restricted {
+            links[a].push((b, d));
+            links[b].push((a, d));
+        }
+    }
+    let mut result = Vec::new();
+    for (c, linked) in links.iter().enumerate() {
+        for picked in combinations(linked.len(), 2) {
+            let ((a, x), (b, y)) = (linked[picked[0]], linked[picked[1]]);
+            if a >= b || x == y || sets[a].overlaps(&sets[b]) {
+                continue;
+            }
+            for z in sets[a].digits & sets[b].digits {
+                if z == x || z == y {
+                    continue;
+                }
+                let eliminations = eliminations(bitmap, z, &[&sets[a], &sets[b]]);
+                if !eliminations.is_empty() {
+                    result.push(AlsDeduction {
+                        kind: AlsKind::XYWing,
+                        sets: vec![sets[a].clone(), sets[b].clone(), sets[c].clone()],
+                        stem: None,
+                        restricted: vec![x, y],
+                        digit: z,
+                        eliminations,
+                    });
+                }
+            }
+        }
+    }
+    result
+}
+
+/// A stem cell with one petal set per digit, each petal's cells for that
+/// digit seeing the stem. Whatever the stem holds, one petal is locked, so a
+/// digit common to all petals goes in one of them.
+pub fn death_blossoms(bitmap: &Bitmap) -> Vec<AlsDeduction> {
+    let sets = almost_locked_sets(bitmap);
+    let mut result = Vec::new();
+    for stem in grid::POINTS {
+        let digits = bitmap.get_variants(stem);
+        if !(2..=3).contains(&digits.count()) {
+            continue;
+        }
+        for z in !digits {
+            let petals: Vec<Vec<&Als>> = digits
+                .into_iter()
+                .map(|d| {
+                    sets.iter()
+                        .filter(|als| als.digits.has_digit(z) && als.digits.has_digit(d))
+                        .filter(|als| !als.points.contains(&stem))
+                        .filter(|als| als.digit_points(bitmap, d).iter().all(|p| p.sees(&stem)))
+                        .collect()
+                })
+                .collect();
+            let mut chosen = Vec::new();
+            blossom(bitmap, stem, digits, z, &petals, &mut chosen, &mut result);
+        }
+    }
+    result
+}
+
+fn blossom<'a>(
+    bitmap: &Bitmap,
+    stem: GridPoint,
+    digits: Variants,
+    z: Digit,
+    petals: &[Vec<&'a Als>],
+    chosen: &mut Vec<&'a Als>,
+    result: &mut Vec<AlsDeduction>,
+) {
+    if chosen.len() == petals.len() {
+        let eliminations = eliminations(bitmap, z, chosen);
+        if !eliminations.is_empty() {
+            result.push(AlsDeduction {
+                kind: AlsKind::DeathBlossom,
+                sets: chosen.iter().map(|&als| als.clone()).collect(),
+                stem: Some(stem),
+                restricted: digits.into_iter().collect(),
+                digit: z,
+                eliminations,
+            });
+        }
+        return;
+    }
+    for &petal in &petals[chosen.len()] {
+        if chosen.iter().any(|other| other.overlaps(petal)) {
+            continue;
+        }
+        chosen.push(petal);
+        blossom(bitmap, stem, digits, z, petals, chosen, result);
+        chosen.pop();
+    }
+}
+
+pub struct AlsTechnique {
+    pub kind: AlsKind,
+}
+
+impl Technique for AlsTechnique {
+    fn name(&self) -> &'static str {
+        als_name(self.kind)
+    }
+
+    fn find(&self, bitmap: &Bitmap) -> Option<Step> {
+        let found = match self.kind {
+            AlsKind::XZ => als_xz(bitmap),
+            AlsKind::XYWing => als_xy_wings(bitmap),
+            AlsKind::DeathBlossom => death_blossoms(bitmap),
+        };
+        found.first().map(|d| d.step(bitmap))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bitmap::Bitmap;
+    use crate::digit;
+    use crate::technique::als::{als_xy_wings, als_xz, death_blossoms, AlsKind};
+    use crate::technique::fixtures::{assert_sound, pair, pencilmarks, point};
+    use crate::technique::Candidate;
+
+    #[test]
+    fn test_als_xz() {
+        let mut bitmap = Bitmap::new();
+        bitmap.set_variants(point(0, 0), pair(digit::ONE, digit::TWO));
+        bitmap.set_variants(point(0, 4), pair(digit::ONE, digit::THREE));
+        bitmap.set_variants(point(5, 4), pair(digit::THREE, digit::TWO));
+
+        let found = als_xz(&bitmap);
+        let target = Candidate { point: point(5, 0), digit: digit::TWO };
+        let deduction = found
+            .iter()
+            .find(|d| d.sets.iter().any(|als| als.points.len() == 2))
+            .unwrap();
+        assert_eq!(deduction.kind, AlsKind::XZ);
+        assert_eq!(deduction.restricted, vec![digit::ONE]);
+        assert_eq!(deduction.eliminations, vec![target]);
+    }
+
+    #[test]
+    fn test_als_xy_wing() {
+        // pivot r1c1, linked by 1 to r1c5 and r1c6 and by 2 to r5c1: one of
+        // the outer sets holds 4, so r5c6 cannot
+        let mut bitmap = Bitmap::new();
+        bitmap.set_variants(point(0, 0), pair(digit::ONE, digit::TWO));
+        bitmap.set_variants(point(0, 4), pair(digit::ONE, digit::THREE));
+        bitmap.set_variants(point(0, 5), pair(digit::THREE, digit::FOUR));
+        bitmap.set_variants(point(4, 0), pair(digit::TWO, digit::FOUR));
+
+        let found = als_xy_wings(&bitmap);
+        let wing = found
+            .iter()
+            .find(|d| d.sets.iter().any(|als| als.points.len() == 2))
+            .unwrap();
+        assert_eq!(wing.kind, AlsKind::XYWing);
+        assert_eq!(wing.sets[2].points, vec![point(0, 0)]);
+        assert_eq!(wing.restricted, vec![digit::ONE, digit::TWO]);
+        assert_eq!(wing.digit, digit::FOUR);
+        assert_eq!(
+            wing.eliminations,
+            vec![Candidate { point: point(4, 5), digit: digit::FOUR }]
+        );
+    }
+
+    #[test]
+    fn test_als_xz_doubly_linked() {
+        // r1c12 and r5c12 linked by 1 in column 1 and 2 in column 2
+        let mut bitmap = Bitmap::new();
+        bitmap.set_variants(point(0, 0), pair(digit::ONE, digit::THREE));
+        bitmap.set_variants(point(0, 1), pair(digit::TWO, digit::THREE));
+        bitmap.set_variants(point(4, 0), pair(digit::ONE, digit::FOUR));
+        bitmap.set_variants(point(4, 1), pair(digit::TWO, digit::FOUR));
+
+        let found = als_xz(&bitmap);
+        let linked: Vec<_> = found
+            .iter()
+            .filter(|d| d.sets[0].points == [point(0, 0), point(0, 1)])
+            .filter(|d| d.sets[1].points == [point(4, 0), point(4, 1)])
+            .collect();
+        assert!(linked.iter().all(|d| d.restricted == [digit::ONE, digit::TWO]));
+        let digits: Vec<_> = linked.iter().map(|d| d.digit).collect();
+        assert_eq!(digits, vec![digit::ONE, digit::TWO, digit::THREE, digit::FOUR]);
+        // the locked digits of each set leave its row and block
+        let three = linked[2];
+        assert_eq!(three.eliminations.len(), 13);
+        for point in [point(0, 8), point(2, 2)] {
+            assert!(three.eliminations.contains(&Candidate { point, digit: digit::THREE }));
+        }
+        assert!(three.eliminations.iter().all(|e| e.point.y.0 < 3));
+    }
+
+    #[test]
+    fn test_death_blossom() {
+        let mut bitmap = Bitmap::new();
+        bitmap.set_variants(point(4, 4), pair(digit::ONE, digit::TWO));
+        bitmap.set_variants(point(4, 0), pair(digit::ONE, digit::FIVE));
+        bitmap.set_variants(point(0, 4), pair(digit::TWO, digit::FIVE));
+
+        let found = death_blossoms(&bitmap);
+        let target = Candidate { point: point(0, 0), digit: digit::FIVE };
+        let blossom = found.iter().find(|d| d.stem == Some(point(4, 4))).unwrap();
+        assert_eq!(blossom.kind, AlsKind::DeathBlossom);
+        assert_eq!(blossom.digit, digit::FIVE);
+        assert_eq!(blossom.eliminations, vec![target]);
+    }
+
+    #[test]
+    fn test_als_in_puzzle() {
+        // .7...4...4..65.......89..6..4.........15....2....328...92..8.76..8..5..1....7....
+        // once singles, intersections, subsets, fish, wings, coloring and chains are used up
+        let bitmap = pencilmarks("
+            13589 7    6    123  12  4   1359 3589  3589
+            4     1238 39   6    5   137 1239 2389  378
+            135   1235 35   8    9   137 1234 6     3457
+            2     4    3579 179  8   19  6    1359  359
+            389   38   1    5    46  69  7    349   2
+            569   56   57   1479 3   2   8    1459  459
+            135   9    2    134  14  8   345  7     6
+            7     36   8    2349 246 5   2349 2349  1
+            1356  1356 4    1239 7   369 2359 23589 3589
+        ");
+        let solution =
+            "876324159419657283325891467243789615981546732657132894592418376738265941164973528";
+
+        let found = als_xz(&bitmap);
+        assert_eq!(found[0].sets[0].points, vec![point(2, 0), point(2, 2)]);
+        assert_eq!(found[0].sets[1].points, vec![point(6, 0), point(7, 1), point(8, 0)]);
+        assert_eq!(found[0].restricted, vec![digit::ONE]);
+        assert_eq!(found[0].eliminations, vec![
+            Candidate { point: point(0, 0), digit: digit::FIVE },
+        ]);
+        let wings = als_xy_wings(&bitmap);
+        for deduction in found.iter().chain(&wings).chain(&death_blossoms(&bitmap)) {
+            assert_sound(solution, &deduction.eliminations);
+        }
+    }
+}
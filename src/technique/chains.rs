@@ -0,0 +1,418 @@
+use crate::bitmap::Bitmap;
+use crate::digit::Digit;
+use crate::grid;
+use crate::grid::GridPoint;
+use crate::technique::{digit_points, outcome, Candidate, Step, Technique};
+use crate::variants::NUM_BITS;
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+
+/// Longest chain, in candidates, the search follows.
+pub const MAX_LENGTH: usize = 20;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ChainKind {
+    /// Every candidate holds the same digit.
+    XChain,
+    /// Strong links only inside bivalue cells, weak links only between cells.
+    XYChain,
+    AIC,
+    /// A discontinuous nice loop: the chain starts and ends at the candidate
+    /// it places.
+    NiceLoop,
+}
+
+/// An alternating inference chain. Links between consecutive `nodes`
+/// alternate strong and weak, starting and ending with a strong one, so one
+/// of the two ends is true.
+#[derive(Clone, Debug)]
+pub struct Chain {
+    pub kind: ChainKind,
+    pub nodes: Vec<Candidate>,
+    pub placements: Vec<Candidate>,
+    pub eliminations: Vec<Candidate>,
+}
+
+/// Strong and weak links between every candidate of the bitmap, indexed by
+/// `cell * NUM_BITS + digit`.
+pub struct Links {
+    pub strong: Vec<Vec<usize>>,
+    pub weak: Vec<Vec<usize>>,
+}
+
+const NUM_CANDIDATES: usize = grid::WIDTH * grid::HEIGHT * NUM_BITS;
+
+pub(crate) fn index(c: Candidate) -> usize {
+    Into::<usize>::into(c.point) * NUM_BITS + usize::from(c.digit)
+}
+
+pub(crate) fn candidate(i: usize) -> Candidate {
+    Candidate {
+        point: GridPoint::from(i / NUM_BITS),
+        digit: Digit::try_from((i % NUM_BITS) as u8).unwrap(),
+    }
+}
+
+/// Both candidates cannot be true at once.
+pub fn weakly_linked(a: Candidate, b: Candidate) -> bool {
+    a != b && (a.point == b.point || a.digit == b.digit && a.point.sees(&b.point))
+}
+
+impl Links {
+    pub fn new(bitmap: &Bitmap) -> Links {
+        let mut strong = vec![Vec::new(); NUM_CANDIDATES];
+        let mut weak = vec![Vec::new(); NUM_CANDIDATES];
+        let link = |links: &mut Vec<Vec<usize>>, a: Candidate, b: Candidate| {
+            let (a, b) = (index(a), index(b));
+            if !links[a].contains(&b) {
+                links[a].push(b);
+                links[b].push(a);
+            }
+        };
+        for point in grid::POINTS {
+            let variants = bitmap.get_variants(point);
+            if variants.count() == 2 {
+                let digits: Vec<Digit> = variants.into_iter().collect();
+                link(
+                    &mut strong,
+                    Candidate { point, digit: digits[0] },
+                    Candidate { point, digit: digits[1] },
+                );
+            }
+            if variants.count() < 2 {
+                continue;
+            }
+            for digit in variants {
+                let a = Candidate { point, digit };
+                for other in variants {
+                    if usize::from(other) > usize::from(digit) {
+                        link(&mut weak, a, Candidate { point, digit: other });
+                    }
+                }
+                for peer in point.peers() {
+                    if Into::<usize>::into(peer) > Into::<usize>::into(point)
+                        && bitmap.has_variant(peer, digit)
+                        && bitmap.get_variants(peer).count() > 1
+                    {
+                        link(&mut weak, a, Candidate { point: peer, digit });
+                    }
+                }
+            }
+        }
+        for unit in grid::units() {
+            for digit in crate::variants::ANY {
+                let points = digit_points(bitmap, unit, digit);
+                if points.len() == 2 {
+                    link(
+                        &mut strong,
+                        Candidate { point: points[0], digit },
+                        Candidate { point: points[1], digit },
+                    );
+                }
+            }
+        }
+        Links { strong, weak }
+    }
+
+    /// Drops the links a chain of the kind may not use.
+    pub fn restrict(&mut self, kind: ChainKind) {
+        let allowed: fn(Candidate, Candidate, bool) -> bool = match kind {
+            ChainKind::XChain => |a, b, _| a.digit == b.digit,
+            ChainKind::XYChain => |a, b, strong| (a.point == b.point) == strong,
+            ChainKind::AIC | ChainKind::NiceLoop => return,
+        };
+        for (links, strong) in [(&mut self.strong, true), (&mut self.weak, false)] {
+            for (i, targets) in links.iter_mut().enumerate() {
+                targets.retain(|&j| allowed(candidate(i), candidate(j), strong));
+            }
+        }
+    }
+}
+
+/// Searches for chains from every candidate, keeping the shortest chain to
+/// each conclusion that removes or places something.
+pub fn chains(bitmap: &Bitmap, max_length: usize) -> Vec<Chain> {
+    search(bitmap, &Links::new(bitmap), max_length)
+}
+
+/// Chains of one kind only, searched over the links that kind may use.
+pub fn chains_of(bitmap: &Bitmap, kind: ChainKind, max_length: usize) -> Vec<Chain> {
+    let mut links = Links::new(bitmap);
+    links.restrict(kind);
+    search(bitmap, &links, max_length)
+        .into_iter()
+        .filter(|chain| chain.kind == kind)
+        .collect()
+}
+
+fn search(bitmap: &Bitmap, links: &Links, max_length: usize) -> Vec<Chain> {
+    let mut result = Vec::new();
+    for start in 0..NUM_CANDIDATES {
+        if links.strong[start].is_empty() {
+            continue;
+        }
+        let around_start: Vec<usize> = links.weak[start].clone();
+        // states: 2 * i for "i is false", 2 * i + 1 for "i is true"
+        let mut parent: Vec<Option<usize>> = vec![None; 2 * NUM_CANDIDATES];
+        let mut depth = vec![0; 2 * NUM_CANDIDATES];
+        let mut queue = VecDeque::from([2 * start]);
+        parent[2 * start] = Some(2 * start);
+        while let Some(state) = queue.pop_front() {
+            let (i, on) = (state / 2, state % 2 == 1);
+            if on {
+                if let Some(chain) = conclude(bitmap, &parent, start, i, &around_start) {
+                    result.push(chain);
+                }
+                if i == start {
+                    continue;
+                }
+            }
+            if depth[state] + 1 >= max_length {
+                continue;
+            }
+            let next = if on { &links.weak[i] } else { &links.strong[i] };
+            for &j in next {
+                let target = 2 * j + !on as usize;
+                if parent[target].is_none() {
+                    parent[target] = Some(state);
+                    depth[target] = depth[state] + 1;
+                    queue.push_back(target);
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Turns a chain from "start is false" to "end is true" into a deduction:
+/// either of them holds.
+fn conclude(
+    bitmap: &Bitmap,
+    parent: &[Option<usize>],
+    start: usize,
+    end: usize,
+    around_start: &[usize],
+) -> Option<Chain> {
+    let (first, last) = (candidate(start), candidate(end));
+    let mut placements = Vec::new();
+    let mut eliminations = Vec::new();
+    if end == start {
+        placements.push(first);
+    } else if start < end {
+        eliminations = around_start
+            .iter()
+            .map(|&i| candidate(i))
+            .filter(|&c| weakly_linked(c, last) && bitmap.has_variant(c.point, c.digit))
+            .collect();
+    }
+    if placements.is_empty() && eliminations.is_empty() {
+        return None;
+    }
+    let mut nodes = Vec::new();
+    let mut state = 2 * end + 1;
+    loop {
+        nodes.push(candidate(state / 2));
+        let previous = parent[state].unwrap();
+        if previous == state {
+            break;
+        }
+        state = previous;
+    }
+    nodes.reverse();
+    Some(Chain {
+        kind: classify(&nodes, !placements.is_empty()),
+        nodes,
+        placements,
+        eliminations,
+    })
+}
+
+fn classify(nodes: &[Candidate], placement: bool) -> ChainKind {
+    if placement {
+        ChainKind::NiceLoop
+    } else if nodes.iter().all(|c| c.digit == nodes[0].digit) {
+        ChainKind::XChain
+    } else if nodes.windows(2).enumerate().all(|(i, pair)| {
+        (i % 2 == 0) == (pair[0].point == pair[1].point)
+    }) {
+        ChainKind::XYChain
+    } else {
+        ChainKind::AIC
+    }
+}
+
+impl Chain {
+    pub fn name(&self) -> &'static str {
+        chain_name(self.kind)
+    }
+
+    pub fn step(&self) -> Step {
+        let mut cells: Vec<GridPoint> = Vec::new();
+        for c in &self.nodes {
+            if !cells.contains(&c.point) {
+                cells.push(c.point);
+            }
+        }
+        Step {
+            name: self.name(),
+            placements: self.placements.clone(),
+            eliminations: self.eliminations.clone(),
+            units: Vec::new(),
+            cells,
+            candidates: self.nodes.clone(),
+            explanation: format!(
+                "{}, so {}",
+                self.notation(),
+                outcome(&self.placements, &self.eliminations)
+            ),
+        }
+    }
+
+    /// Eureka notation: `=` for strong links, `-` for weak ones.
+    pub fn notation(&self) -> String {
+        let mut text = self.nodes[0].to_string();
+        for (i, node) in self.nodes.iter().enumerate().skip(1) {
+            text.push(if i % 2 == 1 { '=' } else { '-' });
+            text.push_str(&node.to_string());
+        }
+        text
+    }
+}
+
+fn chain_name(kind: ChainKind) -> &'static str {
+    match kind {
+        ChainKind::XChain => "X-Chain",
+        ChainKind::XYChain => "XY-Chain",
+        ChainKind::AIC => "AIC",
+        ChainKind::NiceLoop => "Nice Loop",
+    }
+}
+
+/// Chains of one kind up to `max_length` candidates long.
+pub struct Chains {
+    pub kind: ChainKind,
+    pub max_length: usize,
+}
+
+impl Chains {
+    pub fn new(kind: ChainKind) -> Chains {
+        Chains {
+            kind,
+            max_length: MAX_LENGTH,
+        }
+    }
+}
+
+impl Technique for Chains {
+    fn name(&self) -> &'static str {
+        chain_name(self.kind)
+    }
+
+    fn find(&self, bitmap: &Bitmap) -> Option<Step> {
+        chains_of(bitmap, self.kind, self.max_length)
+            .first()
+            .map(Chain::step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bitmap::Bitmap;
+    use crate::digit;
+    use crate::technique::chains::{chains, ChainKind, MAX_LENGTH};
+    use crate::technique::fixtures::{assert_sound, only_at, pair, pencilmarks, point};
+    use crate::technique::Candidate;
+
+    #[test]
+    fn test_x_chain() {
+        // r1c1 = r1c5 - r7c5 = r7c2, r8c1 sees both ends
+        let bitmap = only_at(digit::ONE, &[
+            point(0, 0),
+            point(0, 4),
+            point(6, 4),
+            point(6, 1),
+            point(7, 0),
+            point(4, 0),
+            point(8, 2),
+        ]);
+
+        let found = chains(&bitmap, MAX_LENGTH);
+        let target = Candidate { point: point(7, 0), digit: digit::ONE };
+        let chain = found
+            .iter()
+            .find(|c| c.eliminations.contains(&target))
+            .unwrap();
+        assert_eq!(chain.kind, ChainKind::XChain);
+        assert_eq!(chain.nodes.len(), 4);
+    }
+
+    #[test]
+    fn test_xy_chain() {
+        let mut bitmap = Bitmap::new();
+        bitmap.set_variants(point(0, 0), pair(digit::ONE, digit::TWO));
+        bitmap.set_variants(point(0, 4), pair(digit::TWO, digit::THREE));
+        bitmap.set_variants(point(4, 4), pair(digit::THREE, digit::ONE));
+
+        let found = chains(&bitmap, MAX_LENGTH);
+        let eliminations: Vec<Candidate> =
+            found.iter().flat_map(|c| c.eliminations.clone()).collect();
+        assert_eq!(
+            eliminations,
+            vec![Candidate { point: point(4, 0), digit: digit::ONE }]
+        );
+        assert_eq!(found[0].kind, ChainKind::XYChain);
+        assert_eq!(found[0].nodes.len(), 6);
+    }
+
+    #[test]
+    fn test_nice_loop_placement() {
+        // without 1 in r1c1 it goes to r1c6, then r5c2, which takes it from r4c1
+        // and forces it back into r1c1
+        let bitmap = only_at(digit::ONE, &[
+            point(0, 0),
+            point(0, 5),
+            point(4, 5),
+            point(4, 1),
+            point(3, 0),
+            point(7, 5),
+        ]);
+
+        let found = chains(&bitmap, MAX_LENGTH);
+        let loops: Vec<_> = found.iter().filter(|c| c.kind == ChainKind::NiceLoop).collect();
+        let expected = Candidate { point: point(0, 0), digit: digit::ONE };
+        assert!(loops.iter().any(|c| c.placements == vec![expected]));
+        assert!(found
+            .iter()
+            .flat_map(|c| c.placements.iter())
+            .all(|&c| c == expected || c.point == point(4, 1) || c.point == point(7, 5)));
+    }
+
+    #[test]
+    fn test_chain_in_puzzle() {
+        // .....1..74......39.21...4..94.......1..9...85.5..3....8...4....6....8..2....25.6.
+        // once singles, intersections, subsets, fish, wings and coloring are used up
+        let bitmap = pencilmarks("
+            5 3   9  4   68  1  68  2   7
+            4 8   6  2   5   7  1   3   9
+            7 2   1  38  689 39 4   5   68
+            9 4   78 5   18  2  36  17  36
+            1 6   3  9   7   4  2   8   5
+            2 5   78 18  3   6  79  179 4
+            8 179 2  6   4   39 5   79  13
+            6 179 5  137 19  8  379 4   2
+            3 179 4  17  2   5  789 6   18
+        ");
+        let solution =
+            "539461827486257139721389456948512673163974285257836914872643591615798342394125768";
+
+        let found = chains(&bitmap, MAX_LENGTH);
+        assert_eq!(found[0].kind, ChainKind::AIC);
+        assert_eq!(found[0].nodes.len(), 8);
+        assert_eq!(found[0].eliminations, vec![
+            Candidate { point: point(0, 6), digit: digit::SIX },
+        ]);
+        for chain in &found {
+            assert_sound(solution, &chain.eliminations);
+        }
+    }
+}
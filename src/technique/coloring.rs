@@ -0,0 +1,373 @@
+use crate::bitmap::Bitmap;
+use crate::digit::Digit;
+use crate::grid;
+use crate::grid::GridPoint;
+use crate::technique::{combinations, digit_points, list, outcome, Candidate, Step, Technique};
+use crate::variants;
+use std::collections::VecDeque;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ColoringKind {
+    /// A cell outside the cluster sees both colors.
+    Trap,
+    /// Two cells of one color see each other, so that color is false.
+    Wrap,
+    /// Two clusters linked by a weak link between their colors.
+    Multi,
+}
+
+/// Cells joined by strong links on one digit, split into the two colors
+/// that alternate along the links: exactly one color holds the digit.
+pub type Cluster = [Vec<GridPoint>; 2];
+
+#[derive(Clone, Debug)]
+pub struct Coloring {
+    pub kind: ColoringKind,
+    pub digit: Digit,
+    pub clusters: Vec<Cluster>,
+    pub eliminations: Vec<Candidate>,
+}
+
+impl Coloring {
+    pub fn name(&self) -> &'static str {
+        match self.kind {
+            ColoringKind::Trap => "Color Trap",
+            ColoringKind::Wrap => "Color Wrap",
+            ColoringKind::Multi => "Multi-Coloring",
+        }
+    }
+
+    pub fn step(&self) -> Step {
+        let cells: Vec<GridPoint> = self
+            .clusters
+            .iter()
+            .flat_map(|cluster| cluster.iter().flatten())
+            .copied()
+            .collect();
+        Step {
+            name: self.name(),
+            placements: Vec::new(),
+            eliminations: self.eliminations.clone(),
+            units: Vec::new(),
+            candidates: cells
+                .iter()
+                .map(|&point| Candidate {
+                    point,
+                    digit: self.digit,
+                })
+                .collect(),
+            cells,
+            explanation: self.explanation(),
+        }
+    }
+
+    fn explanation(&self) -> String {
+        let colors: Vec<String> = self
+            .clusters
+            .iter()
+            .map(|[a, b]| format!("{} against {}", list(a.iter()), list(b.iter())))
+            .collect();
+        let reason = match self.kind {
+            ColoringKind::Trap => "one color holds it",
+            ColoringKind::Wrap => "a color that sees itself is false",
+            ColoringKind::Multi => "the clusters are linked",
+        };
+        format!(
+            "coloring {} as {}: {}, so {}",
+            char::from(self.digit),
+            colors.join("; "),
+            reason,
+            outcome(&[], &self.eliminations)
+        )
+    }
+}
+
+/// Connected components of the strong link graph of the digit.
+pub fn clusters(bitmap: &Bitmap, digit: Digit) -> Vec<Cluster> {
+    let mut links: Vec<Vec<usize>> = vec![Vec::new(); grid::WIDTH * grid::HEIGHT];
+    for unit in grid::units() {
+        let points = digit_points(bitmap, unit, digit);
+        if points.len() == 2 {
+            let (a, b): (usize, usize) = (points[0].into(), points[1].into());
+            links[a].push(b);
+            links[b].push(a);
+        }
+    }
+    let mut colors: Vec<Option<usize>> = vec![None; links.len()];
+    let mut result = Vec::new();
+    for start in 0..links.len() {
+        if links[start].is_empty() || colors[start].is_some() {
+            continue;
+        }
+        let mut cluster: Cluster = [Vec::new(), Vec::new()];
+        let mut queue = VecDeque::from([start]);
+        colors[start] = Some(0);
+        while let Some(i) = queue.pop_front() {
+            let color = colors[i].unwrap();
+            cluster[color].push(GridPoint::from(i));
+            for &j in &links[i] {
+                if colors[j].is_none() {
+                    colors[j] = Some(1 - color);
+                    queue.push_back(j);
+                }
+            }
+        }
+        result.push(cluster);
+    }
+    result
+}
+
+fn sees_any(p: GridPoint, points: &[GridPoint]) -> bool {
+    points.iter().any(|q| q.sees(&p))
+}
+
+fn digit_cells(bitmap: &Bitmap, digit: Digit) -> Vec<GridPoint> {
+    grid::POINTS
+        .into_iter()
+        .filter(|&p| bitmap.has_variant(p, digit))
+        .collect()
+}
+
+fn candidates(points: impl IntoIterator<Item = GridPoint>, digit: Digit) -> Vec<Candidate> {
+    points
+        .into_iter()
+        .map(|point| Candidate { point, digit })
+        .collect()
+}
+
+pub fn simple_coloring(bitmap: &Bitmap) -> Vec<Coloring> {
+    let mut result = Vec::new();
+    for digit in variants::ANY {
+        let cells = digit_cells(bitmap, digit);
+        for cluster in clusters(bitmap, digit) {
+            for color in 0..2 {
+                let class = &cluster[color];
+                if class.iter().any(|&p| sees_any(p, class)) {
+                    result.push(Coloring {
+                        kind: ColoringKind::Wrap,
+                        digit,
+                        clusters: vec![cluster.clone()],
+                        eliminations: candidates(class.iter().copied(), digit),
+                    });
+                }
+            }
+            let trapped = cells
+                .iter()
+                .copied()
+                .filter(|p| !cluster[0].contains(p) && !cluster[1].contains(p))
+                .filter(|&p| sees_any(p, &cluster[0]) && sees_any(p, &cluster[1]));
+            let eliminations = candidates(trapped, digit);
+            if !eliminations.is_empty() {
+                result.push(Coloring {
+                    kind: ColoringKind::Trap,
+                    digit,
+                    clusters: vec![cluster],
+                    eliminations,
+                });
+            }
+        }
+    }
+    result
+}
+
+/// If a color of one cluster sees a color of another, at least one of the
+/// opposite colors is true. Cells seeing both opposite colors lose the digit,
+/// and a color seeing both colors of the other cluster is false. Both rules
+/// are applied to every link between the two clusters.
+pub fn multi_coloring(bitmap: &Bitmap) -> Vec<Coloring> {
+    let mut result = Vec::new();
+    for digit in variants::ANY {
+        let cells = digit_cells(bitmap, digit);
+        let all = clusters(bitmap, digit);
+        for picked in combinations(all.len(), 2) {
+            let (first, second) = (&all[picked[0]], &all[picked[1]]);
+            let links = |ca: usize, cb: usize| first[ca].iter().any(|&p| sees_any(p, &second[cb]));
+            let mut eliminated: Vec<GridPoint> = Vec::new();
+            for ca in 0..2 {
+                for cb in 0..2 {
+                    if !links(ca, cb) {
+                        continue;
+                    }
+                    let (x, y) = (&first[1 - ca], &second[1 - cb]);
+                    eliminated.extend(
+                        cells
+                            .iter()
+                            .copied()
+                            .filter(|&p| sees_any(p, x) && sees_any(p, y)),
+                    );
+                }
+                if links(ca, 0) && links(ca, 1) {
+                    eliminated.extend(first[ca].iter().copied());
+                }
+                if links(0, ca) && links(1, ca) {
+                    eliminated.extend(second[ca].iter().copied());
+                }
+            }
+            eliminated.sort_by_key(|&p| Into::<usize>::into(p));
+            eliminated.dedup();
+            if !eliminated.is_empty() {
+                result.push(Coloring {
+                    kind: ColoringKind::Multi,
+                    digit,
+                    clusters: vec![first.clone(), second.clone()],
+                    eliminations: candidates(eliminated, digit),
+                });
+            }
+        }
+    }
+    result
+}
+
+/// Color traps and color wraps.
+pub struct SimpleColoring;
+
+impl Technique for SimpleColoring {
+    fn name(&self) -> &'static str {
+        "Simple Coloring"
+    }
+
+    fn find(&self, bitmap: &Bitmap) -> Option<Step> {
+        simple_coloring(bitmap).first().map(Coloring::step)
+    }
+}
+
+pub struct MultiColoring;
+
+impl Technique for MultiColoring {
+    fn name(&self) -> &'static str {
+        "Multi-Coloring"
+    }
+
+    fn find(&self, bitmap: &Bitmap) -> Option<Step> {
+        multi_coloring(bitmap).first().map(Coloring::step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::digit;
+    use crate::grid::GridPoint;
+    use crate::technique::coloring::{multi_coloring, simple_coloring, ColoringKind};
+    use crate::technique::fixtures::{assert_sound, only_at, pencilmarks, point};
+    use crate::technique::Candidate;
+
+    fn eliminated(found: &[crate::technique::coloring::Coloring]) -> Vec<GridPoint> {
+        found
+            .iter()
+            .flat_map(|c| c.eliminations.iter().map(|e| e.point))
+            .collect()
+    }
+
+    #[test]
+    fn test_color_trap() {
+        // chain r1c1 - r1c5 - r7c5 - r7c2, trapped r8c1 with fillers r5c1 and r9c3
+        let bitmap = only_at(digit::FIVE, &[
+            point(0, 0),
+            point(0, 4),
+            point(6, 4),
+            point(6, 1),
+            point(7, 0),
+            point(4, 0),
+            point(8, 2),
+        ]);
+
+        let found = simple_coloring(&bitmap);
+        assert!(found.iter().all(|c| c.kind == ColoringKind::Trap));
+        assert_eq!(eliminated(&found), vec![point(7, 0)]);
+    }
+
+    #[test]
+    fn test_color_wrap() {
+        // chain r1c1 - r1c5 - r7c5 - r7c2 - r3c2 where r1c1 and r3c2 share a block
+        let bitmap = only_at(digit::FIVE, &[
+            point(0, 0),
+            point(0, 4),
+            point(6, 4),
+            point(6, 1),
+            point(2, 1),
+            point(1, 2),
+        ]);
+
+        let found = simple_coloring(&bitmap);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].kind, ColoringKind::Wrap);
+        let mut points = eliminated(&found);
+        points.sort_by_key(|&p| Into::<usize>::into(p));
+        assert_eq!(points, vec![point(0, 0), point(2, 1), point(6, 4)]);
+    }
+
+    #[test]
+    fn test_multi_coloring() {
+        // clusters r1c1 - r1c7 and r5c1 - r5c9 weakly linked in column 1
+        let bitmap = only_at(digit::FIVE, &[
+            point(0, 0),
+            point(0, 6),
+            point(4, 0),
+            point(4, 8),
+            point(7, 0),
+            point(1, 8),
+            point(8, 8),
+            point(2, 7),
+        ]);
+
+        assert!(eliminated(&simple_coloring(&bitmap)).is_empty());
+        let found = multi_coloring(&bitmap);
+        assert!(found.iter().all(|c| c.kind == ColoringKind::Multi));
+        assert_eq!(eliminated(&found), vec![point(1, 8)]);
+    }
+
+    #[test]
+    fn test_multi_coloring_every_link() {
+        // clusters r4c2 / r4c6 - r8c2 and r6c5 / r8c5: r4c6 sees r6c5 and r8c2 sees r8c5,
+        // so r4c6 and r8c2 are false and cells seeing r4c2 and either r6c5 or r8c5 lose it
+        let bitmap = only_at(digit::FIVE, &[
+            point(3, 1),
+            point(3, 5),
+            point(7, 1),
+            point(5, 4),
+            point(7, 4),
+            point(5, 0),
+            point(5, 2),
+            point(5, 3),
+            point(7, 6),
+            point(2, 7),
+        ]);
+
+        assert!(eliminated(&simple_coloring(&bitmap)).is_empty());
+        let found = multi_coloring(&bitmap);
+        assert_eq!(found.len(), 1);
+        assert_eq!(eliminated(&found), vec![point(3, 5), point(5, 0), point(5, 2), point(7, 1)]);
+    }
+
+    #[test]
+    fn test_coloring_in_puzzle() {
+        // ..34..67..6.3....8....9...3.......5.1...5.7..2.7.148......6....7....3.9...28....5
+        // once singles, intersections, subsets, fish and wings are used up
+        let bitmap = pencilmarks("
+            59   2   3    4    8  15   6     7   19
+            459  6   1459 3    27 1257 12459 124 8
+            458  7   1458 1256 9  1256 1245  124 3
+            4689 489 4689 7    3  289  1249  5   1249
+            1    3   489  269  5  2689 7     246 2469
+            2    5   7    69   1  4    8     3   69
+            3459 149 459  1259 6  1259 1234  8   7
+            7    148 4568 125  24 3    124   9   1246
+            3469 149 2    8    47 79   134   146 5
+        ");
+        let solution =
+            "923485671561372948874196523486739152139258764257614839345961287718523496692847315";
+
+        let found = simple_coloring(&bitmap);
+        assert_eq!(found[0].kind, ColoringKind::Trap);
+        assert_eq!(found[0].clusters, vec![[
+            vec![point(0, 0), point(1, 6)],
+            vec![point(0, 8), point(3, 6)],
+        ]]);
+        assert_eq!(found[0].eliminations, vec![
+            Candidate { point: point(3, 0), digit: digit::NINE },
+        ]);
+        for coloring in simple_coloring(&bitmap).iter().chain(&multi_coloring(&bitmap)) {
+            assert_sound(solution, &coloring.eliminations);
+        }
+    }
+}
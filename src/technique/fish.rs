@@ -0,0 +1,363 @@
+use crate::bitmap::Bitmap;
+use crate::digit::Digit;
+use crate::grid;
+use crate::grid::{GridColumn, GridPoint, GridRow, GridUnit};
+use crate::technique::{
+    combinations, digit_mask, is_solved, list, outcome, Candidate, Step, Technique,
+};
+use crate::variants;
+
+pub const MIN_SIZE: usize = 2;
+pub const MAX_SIZE: usize = 4;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FishKind {
+    Basic,
+    /// Some base cells lie off the cover lines, all inside one block.
+    Finned,
+    /// A finned fish where a base line keeps fewer than two cover cells.
+    Sashimi,
+}
+
+/// `size` base lines whose cells for the digit all fall on `size` cover
+/// lines, so the digit leaves the rest of the cover lines. With fins only the
+/// cover cells that see every fin lose the digit.
+#[derive(Clone, Debug)]
+pub struct Fish {
+    pub kind: FishKind,
+    pub digit: Digit,
+    pub base: Vec<GridUnit>,
+    pub cover: Vec<GridUnit>,
+    pub fins: Vec<GridPoint>,
+    pub eliminations: Vec<Candidate>,
+}
+
+impl Fish {
+    pub fn name(&self) -> &'static str {
+        match self.base.len() {
+            2 => "X-Wing",
+            3 => "Swordfish",
+            _ => "Jellyfish",
+        }
+    }
+
+    /// The name with the kind of fish spelled out, e.g. "Sashimi Swordfish".
+    pub fn full_name(&self) -> &'static str {
+        full_name(self.kind, self.base.len())
+    }
+
+    pub fn step(&self, bitmap: &Bitmap) -> Step {
+        let cells: Vec<GridPoint> = self
+            .base
+            .iter()
+            .flat_map(|unit| unit.points())
+            .filter(|&p| bitmap.has_variant(p, self.digit))
+            .collect();
+        Step {
+            name: self.full_name(),
+            placements: Vec::new(),
+            eliminations: self.eliminations.clone(),
+            units: [self.base.clone(), self.cover.clone()].concat(),
+            candidates: cells
+                .iter()
+                .map(|&point| Candidate {
+                    point,
+                    digit: self.digit,
+                })
+                .collect(),
+            cells,
+            explanation: self.explanation(),
+        }
+    }
+
+    fn explanation(&self) -> String {
+        let fins = if self.fins.is_empty() {
+            String::new()
+        } else {
+            format!(" apart from the fins {}", list(self.fins.iter()))
+        };
+        format!(
+            "{} in {} lies only in {}{}, so {}",
+            char::from(self.digit),
+            list(self.base.iter()),
+            list(self.cover.iter()),
+            fins,
+            outcome(&[], &self.eliminations)
+        )
+    }
+
+    /// Fins justifying the removal of a candidate: the fins its cell sees.
+    pub fn fins_seen_by(&self, c: Candidate) -> Vec<GridPoint> {
+        self.fins
+            .iter()
+            .copied()
+            .filter(|fin| fin.sees(&c.point))
+            .collect()
+    }
+}
+
+/// Row based fish followed by column based ones.
+pub fn basic_fish(bitmap: &Bitmap, size: usize) -> Vec<Fish> {
+    let mut result = Vec::new();
+    for rows in [true, false] {
+        for digit in variants::ANY {
+            let lines: Vec<(GridUnit, u16)> = (0..grid::WIDTH)
+                .map(|i| line(rows, i))
+                .map(|unit| (unit, digit_mask(bitmap, unit, digit)))
+                .filter(|(_, mask)| (MIN_SIZE..=size).contains(&(mask.count_ones() as usize)))
+                .collect();
+            for picked in combinations(lines.len(), size) {
+                let covered = picked.iter().fold(0, |acc, &i| acc | lines[i].1);
+                if covered.count_ones() as usize != size {
+                    continue;
+                }
+                let base: Vec<GridUnit> = picked.iter().map(|&i| lines[i].0).collect();
+                let cover: Vec<GridUnit> = (0..grid::WIDTH)
+                    .filter(|i| covered & 1 << i != 0)
+                    .map(|i| line(!rows, i))
+                    .collect();
+                let eliminations = cover_eliminations(bitmap, digit, &base, &cover, |_| true);
+                if !eliminations.is_empty() {
+                    result.push(Fish {
+                        kind: FishKind::Basic,
+                        digit,
+                        base,
+                        cover,
+                        fins: Vec::new(),
+                        eliminations,
+                    });
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Fish whose base lines stray off the cover lines into a single block.
+pub fn finned_fish(bitmap: &Bitmap, size: usize) -> Vec<Fish> {
+    let mut result = Vec::new();
+    for rows in [true, false] {
+        for digit in variants::ANY {
+            let lines: Vec<(GridUnit, u16)> = (0..grid::WIDTH)
+                .map(|i| line(rows, i))
+                .map(|unit| (unit, digit_mask(bitmap, unit, digit)))
+                .filter(|(unit, mask)| *mask != 0 && !is_placed(bitmap, *unit, *mask))
+                .filter(|(_, mask)| mask.count_ones() as usize <= size + grid::BLOCK_SIZE)
+                .collect();
+            for picked in combinations(lines.len(), size) {
+                let covered = picked.iter().fold(0u16, |acc, &i| acc | lines[i].1);
+                let crossing: Vec<usize> =
+                    (0..grid::WIDTH).filter(|i| covered & 1 << i != 0).collect();
+                if crossing.len() <= size || crossing.len() > size + grid::BLOCK_SIZE {
+                    continue;
+                }
+                for chosen in combinations(crossing.len(), size) {
+                    let cover_mask = chosen.iter().fold(0u16, |acc, &i| acc | 1 << crossing[i]);
+                    let base: Vec<GridUnit> = picked.iter().map(|&i| lines[i].0).collect();
+                    let fins: Vec<GridPoint> = picked
+                        .iter()
+                        .flat_map(|&i| {
+                            let (unit, mask) = lines[i];
+                            let points = unit.points();
+                            (0..grid::WIDTH)
+                                .filter(move |j| mask & !cover_mask & 1 << j != 0)
+                                .map(move |j| points[j])
+                        })
+                        .collect();
+                    let block = fins[0].block();
+                    if fins.iter().any(|fin| fin.block() != block) {
+                        continue;
+                    }
+                    let cover: Vec<GridUnit> = (0..grid::WIDTH)
+                        .filter(|i| cover_mask & 1 << i != 0)
+                        .map(|i| line(!rows, i))
+                        .collect();
+                    let eliminations =
+                        cover_eliminations(bitmap, digit, &base, &cover, |p| p.block() == block);
+                    if eliminations.is_empty() {
+                        continue;
+                    }
+                    let sashimi = picked
+                        .iter()
+                        .any(|&i| (lines[i].1 & cover_mask).count_ones() < 2);
+                    result.push(Fish {
+                        kind: if sashimi {
+                            FishKind::Sashimi
+                        } else {
+                            FishKind::Finned
+                        },
+                        digit,
+                        base,
+                        cover,
+                        fins,
+                        eliminations,
+                    });
+                }
+            }
+        }
+    }
+    result
+}
+
+/// A line whose only cell for the digit already holds it.
+fn is_placed(bitmap: &Bitmap, unit: GridUnit, mask: u16) -> bool {
+    mask.count_ones() == 1
+        && is_solved(bitmap.get_variants(unit.points()[mask.trailing_zeros() as usize]))
+}
+
+fn line(rows: bool, i: usize) -> GridUnit {
+    if rows {
+        GridUnit::Row(GridRow(i))
+    } else {
+        GridUnit::Column(GridColumn(i))
+    }
+}
+
+/// Candidates of the digit on the cover lines outside the base lines that
+/// pass `filter`.
+fn cover_eliminations(
+    bitmap: &Bitmap,
+    digit: Digit,
+    base: &[GridUnit],
+    cover: &[GridUnit],
+    filter: impl Fn(GridPoint) -> bool,
+) -> Vec<Candidate> {
+    cover
+        .iter()
+        .flat_map(|unit| unit.points())
+        .filter(|&p| bitmap.has_variant(p, digit))
+        .filter(|&p| !base.iter().any(|unit| unit.contains(p)))
+        .filter(|&p| filter(p))
+        .map(|point| Candidate { point, digit })
+        .collect()
+}
+
+fn full_name(kind: FishKind, size: usize) -> &'static str {
+    match (kind, size) {
+        (FishKind::Basic, 2) => "X-Wing",
+        (FishKind::Basic, 3) => "Swordfish",
+        (FishKind::Basic, _) => "Jellyfish",
+        (FishKind::Finned, 2) => "Finned X-Wing",
+        (FishKind::Finned, 3) => "Finned Swordfish",
+        (FishKind::Finned, _) => "Finned Jellyfish",
+        (FishKind::Sashimi, 2) => "Sashimi X-Wing",
+        (FishKind::Sashimi, 3) => "Sashimi Swordfish",
+        (FishKind::Sashimi, _) => "Sashimi Jellyfish",
+    }
+}
+
+pub struct BasicFish {
+    pub size: usize,
+}
+
+impl Technique for BasicFish {
+    fn name(&self) -> &'static str {
+        full_name(FishKind::Basic, self.size)
+    }
+
+    fn find(&self, bitmap: &Bitmap) -> Option<Step> {
+        basic_fish(bitmap, self.size)
+            .first()
+            .map(|f| f.step(bitmap))
+    }
+}
+
+/// Finned and sashimi fish of one size.
+pub struct FinnedFish {
+    pub size: usize,
+}
+
+impl Technique for FinnedFish {
+    fn name(&self) -> &'static str {
+        full_name(FishKind::Finned, self.size)
+    }
+
+    fn find(&self, bitmap: &Bitmap) -> Option<Step> {
+        finned_fish(bitmap, self.size)
+            .first()
+            .map(|f| f.step(bitmap))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bitmap::Bitmap;
+    use crate::digit;
+    use crate::grid::{GridColumn, GridPoint, GridRow, GridUnit};
+    use crate::technique::fish::{basic_fish, finned_fish, FishKind};
+    use crate::technique::Candidate;
+
+    fn keep_only(bitmap: &mut Bitmap, y: GridRow, columns: &[usize]) {
+        for p in GridUnit::Row(y).points() {
+            if !columns.contains(&p.column().0) {
+                bitmap.remove_variant(p, digit::FOUR);
+            }
+        }
+    }
+
+    #[test]
+    fn test_x_wing() {
+        let mut bitmap = Bitmap::new();
+        for y in [GridRow(1), GridRow(6)] {
+            for p in GridUnit::Row(y).points() {
+                if p.column() != GridColumn(2) && p.column() != GridColumn(7) {
+                    bitmap.remove_variant(p, digit::FOUR);
+                }
+            }
+        }
+
+        let found = basic_fish(&bitmap, 2);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name(), "X-Wing");
+        assert_eq!(found[0].digit, digit::FOUR);
+        assert_eq!(
+            found[0].base,
+            vec![GridUnit::Row(GridRow(1)), GridUnit::Row(GridRow(6))]
+        );
+        assert_eq!(
+            found[0].cover,
+            vec![GridUnit::Column(GridColumn(2)), GridUnit::Column(GridColumn(7))]
+        );
+        assert_eq!(found[0].eliminations.len(), 2 * 7);
+        assert!(basic_fish(&bitmap, 3).is_empty());
+        assert!(finned_fish(&bitmap, 2).is_empty());
+    }
+
+    #[test]
+    fn test_finned_x_wing() {
+        let mut bitmap = Bitmap::new();
+        keep_only(&mut bitmap, GridRow(1), &[2, 7]);
+        keep_only(&mut bitmap, GridRow(6), &[2, 7, 8]);
+
+        assert!(basic_fish(&bitmap, 2).is_empty());
+        let found = finned_fish(&bitmap, 2);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].kind, FishKind::Finned);
+        let fin = GridPoint { x: GridColumn(8), y: GridRow(6) };
+        assert_eq!(found[0].fins, vec![fin]);
+        let eliminated: Vec<GridPoint> = found[0].eliminations.iter().map(|c| c.point).collect();
+        assert_eq!(
+            eliminated,
+            vec![
+                GridPoint { x: GridColumn(7), y: GridRow(7) },
+                GridPoint { x: GridColumn(7), y: GridRow(8) },
+            ]
+        );
+        assert_eq!(found[0].fins_seen_by(found[0].eliminations[0]), vec![fin]);
+    }
+
+    #[test]
+    fn test_sashimi_x_wing() {
+        let mut bitmap = Bitmap::new();
+        keep_only(&mut bitmap, GridRow(1), &[2, 7]);
+        keep_only(&mut bitmap, GridRow(6), &[2, 8]);
+
+        let found = finned_fish(&bitmap, 2);
+        assert!(found.iter().all(|f| f.kind == FishKind::Sashimi));
+        let expected = Candidate {
+            point: GridPoint { x: GridColumn(7), y: GridRow(7) },
+            digit: digit::FOUR,
+        };
+        assert!(found.iter().any(|f| f.eliminations.contains(&expected)));
+    }
+}
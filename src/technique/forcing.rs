@@ -0,0 +1,439 @@
+use crate::bitmap::Bitmap;
+use crate::grid;
+use crate::grid::GridPoint;
+use crate::technique::chains::{candidate, index};
+use crate::technique::{digit_points, is_solved, list, outcome, Candidate, Step, Technique};
+use crate::variants;
+use crate::variants::{Variants, NUM_BITS};
+use std::collections::VecDeque;
+
+/// Largest number of branches a single forcing deduction follows.
+pub const MAX_BRANCHES: usize = 4;
+
+const NUM_CANDIDATES: usize = grid::WIDTH * grid::HEIGHT * NUM_BITS;
+
+/// A consequence of an assumption: the candidate is placed or removed
+/// because of earlier implications, given as indices into the same branch.
+#[derive(Clone, Debug)]
+pub struct Implication {
+    pub candidate: Candidate,
+    pub placed: bool,
+    pub causes: Vec<usize>,
+}
+
+/// Everything that follows from assuming one candidate true. The first
+/// implication is the assumption itself.
+#[derive(Clone, Debug)]
+pub struct Branch {
+    pub assumption: Candidate,
+    pub implications: Vec<Implication>,
+    /// Implications that left a cell or a unit without any candidate.
+    pub contradiction: Option<Vec<usize>>,
+}
+
+impl Branch {
+    /// Assumes the candidate and follows singles until nothing changes or
+    /// something breaks. With `digit_only` just the assumed digit is
+    /// followed, as Nishio does.
+    pub fn new(bitmap: &Bitmap, assumption: Candidate, digit_only: bool) -> Branch {
+        let mut work = *bitmap;
+        let mut branch = Branch {
+            assumption,
+            implications: Vec::new(),
+            contradiction: None,
+        };
+        let mut placed: Vec<bool> = grid::POINTS
+            .into_iter()
+            .map(|p| is_solved(bitmap.get_variants(p)))
+            .collect();
+        let mut eliminated: Vec<Option<usize>> = vec![None; NUM_CANDIDATES];
+        let mut pending = VecDeque::from([branch.place(assumption, Vec::new(), &mut placed)]);
+        let digits = if digit_only {
+            Variants::from(assumption.digit)
+        } else {
+            variants::ANY
+        };
+        loop {
+            while let Some(i) = pending.pop_front() {
+                let c = branch.implications[i].candidate;
+                if !work.has_variant(c.point, c.digit) {
+                    branch.contradiction = Some(vec![i]);
+                    return branch;
+                }
+                let mut targets: Vec<Candidate> = c
+                    .point
+                    .peers()
+                    .into_iter()
+                    .filter(|&p| work.has_variant(p, c.digit))
+                    .map(|point| Candidate { point, digit: c.digit })
+                    .collect();
+                if !digit_only {
+                    targets.extend(
+                        work.get_variants(c.point)
+                            .into_iter()
+                            .filter(|&d| d != c.digit)
+                            .map(|digit| Candidate { point: c.point, digit }),
+                    );
+                }
+                for t in targets {
+                    work.remove_variant(t.point, t.digit);
+                    eliminated[index(t)] = Some(branch.implications.len());
+                    branch.implications.push(Implication {
+                        candidate: t,
+                        placed: false,
+                        causes: vec![i],
+                    });
+                }
+            }
+            if !digit_only {
+                for point in grid::POINTS {
+                    let variants = work.get_variants(point);
+                    if variants.count() == 0 {
+                        branch.contradiction = Some(removed_from(&eliminated, &[point], variants::ANY));
+                        return branch;
+                    }
+                    if variants.count() == 1 && !placed[Into::<usize>::into(point)] {
+                        let causes = removed_from(&eliminated, &[point], variants::ANY);
+                        let digit = variants.into_iter().next().unwrap();
+                        pending.push_back(branch.place(Candidate { point, digit }, causes, &mut placed));
+                    }
+                }
+            }
+            for unit in grid::units() {
+                for digit in digits {
+                    let points = digit_points(&work, unit, digit);
+                    let cells = unit.points();
+                    if points.is_empty() {
+                        branch.contradiction = Some(removed_from(&eliminated, &cells, digit.into()));
+                        return branch;
+                    }
+                    if points.len() == 1 && !placed[Into::<usize>::into(points[0])] {
+                        let causes = removed_from(&eliminated, &cells, digit.into());
+                        pending.push_back(branch.place(
+                            Candidate { point: points[0], digit },
+                            causes,
+                            &mut placed,
+                        ));
+                    }
+                }
+            }
+            if pending.is_empty() {
+                return branch;
+            }
+        }
+    }
+}
+
+/// Implications that removed the digits from the cells.
+fn removed_from(eliminated: &[Option<usize>], cells: &[GridPoint], digits: Variants) -> Vec<usize> {
+    cells
+        .iter()
+        .flat_map(|&point| {
+            digits
+                .into_iter()
+                .filter_map(move |digit| eliminated[index(Candidate { point, digit })])
+        })
+        .collect()
+}
+
+impl Branch {
+    fn place(&mut self, c: Candidate, causes: Vec<usize>, placed: &mut [bool]) -> usize {
+        placed[Into::<usize>::into(c.point)] = true;
+        self.implications.push(Implication {
+            candidate: c,
+            placed: true,
+            causes,
+        });
+        self.implications.len() - 1
+    }
+
+    /// The implication placing or removing the candidate, if any.
+    pub fn find(&self, c: Candidate, placed: bool) -> Option<usize> {
+        self.implications
+            .iter()
+            .position(|i| i.candidate == c && i.placed == placed)
+    }
+
+    /// The implication at `i` with everything it follows from, in the order
+    /// they were derived.
+    pub fn trail(&self, i: usize) -> Vec<&Implication> {
+        let mut needed = vec![false; self.implications.len()];
+        let mut stack = vec![i];
+        while let Some(j) = stack.pop() {
+            if !needed[j] {
+                needed[j] = true;
+                stack.extend(self.implications[j].causes.iter().copied());
+            }
+        }
+        self.implications
+            .iter()
+            .zip(needed)
+            .filter(|(_, n)| *n)
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ForcingKind {
+    /// Every candidate of a cell leads to the same result.
+    Cell,
+    /// Every position of a digit in a unit leads to the same result.
+    Unit,
+    /// Assuming the candidate breaks the grid for its own digit.
+    Nishio,
+}
+
+/// Branches covering every possibility of a cell or unit. Candidates whose
+/// branch breaks are removed, and whatever the other branches agree on holds.
+#[derive(Clone, Debug)]
+pub struct Forcing {
+    pub kind: ForcingKind,
+    pub branches: Vec<Branch>,
+    pub placements: Vec<Candidate>,
+    pub eliminations: Vec<Candidate>,
+}
+
+impl Forcing {
+    pub fn name(&self) -> &'static str {
+        forcing_name(self.kind)
+    }
+
+    pub fn step(&self) -> Step {
+        let candidates: Vec<Candidate> = self.branches.iter().map(|b| b.assumption).collect();
+        let mut cells: Vec<GridPoint> = Vec::new();
+        for c in &candidates {
+            if !cells.contains(&c.point) {
+                cells.push(c.point);
+            }
+        }
+        Step {
+            name: self.name(),
+            placements: self.placements.clone(),
+            eliminations: self.eliminations.clone(),
+            units: Vec::new(),
+            explanation: self.explanation(&candidates),
+            cells,
+            candidates,
+        }
+    }
+
+    fn explanation(&self, assumptions: &[Candidate]) -> String {
+        let pattern = match self.kind {
+            ForcingKind::Nishio => format!(
+                "assuming {} leaves no place for {} somewhere",
+                assumptions[0],
+                char::from(assumptions[0].digit)
+            ),
+            _ => format!(
+                "one of {} is true and every one that does not break the grid agrees",
+                list(assumptions.iter())
+            ),
+        };
+        format!(
+            "{}, so {}",
+            pattern,
+            outcome(&self.placements, &self.eliminations)
+        )
+    }
+}
+
+fn forcing_name(kind: ForcingKind) -> &'static str {
+    match kind {
+        ForcingKind::Cell => "Cell Forcing Chain",
+        ForcingKind::Unit => "Unit Forcing Chain",
+        ForcingKind::Nishio => "Nishio",
+    }
+}
+
+fn conclude(kind: ForcingKind, branches: Vec<Branch>) -> Option<Forcing> {
+    let (broken, valid): (Vec<&Branch>, Vec<&Branch>) =
+        branches.iter().partition(|b| b.contradiction.is_some());
+    if valid.is_empty() {
+        return None;
+    }
+    let mut eliminations: Vec<Candidate> = broken.iter().map(|b| b.assumption).collect();
+    let mut placements = Vec::new();
+    for implication in &valid[0].implications {
+        let c = implication.candidate;
+        if !valid[1..].iter().all(|b| b.find(c, implication.placed).is_some()) {
+            continue;
+        }
+        if implication.placed {
+            placements.push(c);
+        } else if !eliminations.contains(&c) {
+            eliminations.push(c);
+        }
+    }
+    if placements.is_empty() && eliminations.is_empty() {
+        return None;
+    }
+    Some(Forcing {
+        kind,
+        branches,
+        placements,
+        eliminations,
+    })
+}
+
+pub fn cell_forcing(bitmap: &Bitmap) -> Vec<Forcing> {
+    grid::POINTS
+        .into_iter()
+        .filter(|&p| (2..=MAX_BRANCHES as u32).contains(&bitmap.get_variants(p).count()))
+        .filter_map(|point| {
+            let branches = bitmap
+                .get_variants(point)
+                .into_iter()
+                .map(|digit| Branch::new(bitmap, Candidate { point, digit }, false))
+                .collect();
+            conclude(ForcingKind::Cell, branches)
+        })
+        .collect()
+}
+
+pub fn unit_forcing(bitmap: &Bitmap) -> Vec<Forcing> {
+    let mut result = Vec::new();
+    for unit in grid::units() {
+        for digit in variants::ANY {
+            let points = digit_points(bitmap, unit, digit);
+            if !(2..=MAX_BRANCHES).contains(&points.len()) {
+                continue;
+            }
+            let branches = points
+                .into_iter()
+                .map(|point| Branch::new(bitmap, Candidate { point, digit }, false))
+                .collect();
+            result.extend(conclude(ForcingKind::Unit, branches));
+        }
+    }
+    result
+}
+
+/// Digit forcing: a candidate whose digit, once assumed there, leaves some
+/// unit without a place for it.
+pub fn nishio(bitmap: &Bitmap) -> Vec<Forcing> {
+    (0..NUM_CANDIDATES)
+        .map(candidate)
+        .filter(|c| bitmap.has_variant(c.point, c.digit))
+        .filter(|c| !is_solved(bitmap.get_variants(c.point)))
+        .map(|c| Branch::new(bitmap, c, true))
+        .filter(|branch| branch.contradiction.is_some())
+        .map(|branch| Forcing {
+            kind: ForcingKind::Nishio,
+            eliminations: vec![branch.assumption],
+            placements: Vec::new(),
+            branches: vec![branch],
+        })
+        .collect()
+}
+
+pub struct ForcingChains {
+    pub kind: ForcingKind,
+}
+
+impl Technique for ForcingChains {
+    fn name(&self) -> &'static str {
+        forcing_name(self.kind)
+    }
+
+    fn find(&self, bitmap: &Bitmap) -> Option<Step> {
+        let found = match self.kind {
+            ForcingKind::Cell => cell_forcing(bitmap),
+            ForcingKind::Unit => unit_forcing(bitmap),
+            ForcingKind::Nishio => nishio(bitmap),
+        };
+        found.first().map(Forcing::step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bitmap::Bitmap;
+    use crate::digit;
+    use crate::grid::{GridRow, GridUnit};
+    use crate::technique::fixtures::{assert_sound, pair, pencilmarks, point};
+    use crate::technique::forcing::{cell_forcing, nishio, unit_forcing, ForcingKind};
+    use crate::technique::Candidate;
+
+    #[test]
+    fn test_nishio() {
+        let mut bitmap = Bitmap::new();
+        for p in GridUnit::Row(GridRow(1)).points() {
+            if p != point(1, 1) && p != point(1, 2) {
+                bitmap.remove_variant(p, digit::ONE);
+            }
+        }
+
+        let found = nishio(&bitmap);
+        let assumed = Candidate { point: point(0, 0), digit: digit::ONE };
+        let forcing = found.iter().find(|f| f.eliminations == vec![assumed]).unwrap();
+        assert_eq!(forcing.kind, ForcingKind::Nishio);
+        let branch = &forcing.branches[0];
+        let broken = branch.contradiction.as_ref().unwrap();
+        assert_eq!(broken.len(), 2);
+        let trail = branch.trail(broken[0]);
+        assert_eq!(trail[0].candidate, assumed);
+        assert!(trail[0].placed);
+        assert_eq!(found.len(), 6);
+    }
+
+    #[test]
+    fn test_cell_forcing() {
+        let mut bitmap = Bitmap::new();
+        bitmap.set_variants(point(4, 4), pair(digit::ONE, digit::TWO));
+        bitmap.set_variants(point(4, 0), pair(digit::ONE, digit::THREE));
+        bitmap.set_variants(point(0, 4), pair(digit::TWO, digit::THREE));
+
+        let found = cell_forcing(&bitmap);
+        let forcing = found
+            .iter()
+            .find(|f| f.branches[0].assumption.point == point(4, 4))
+            .unwrap();
+        let target = Candidate { point: point(0, 0), digit: digit::THREE };
+        assert_eq!(forcing.eliminations, vec![target]);
+        assert!(forcing.placements.is_empty());
+        for branch in &forcing.branches {
+            assert!(branch.contradiction.is_none());
+            let i = branch.find(target, false).unwrap();
+            assert_eq!(branch.trail(i).len(), 4);
+        }
+    }
+
+    #[test]
+    fn test_forcing_in_puzzle() {
+        // .23.6........7.........98..1...3.4....7..4......8267.....6....945......28.25...4.
+        // once every technique but uniqueness and forcing is used up
+        let bitmap = pencilmarks("
+            579 2    3   14   6   8   19  1579  1457
+            569 189  89  34   7   135 2   1569  456
+            567 147  45  2    15  9   8   13567 367
+            1   689  689 79   3   57  4   2     568
+            2   3689 7   19   159 4   369 3569  3568
+            359 349  45  8    2   6   7   139   13
+            37  37   1   6    4   2   5   8     9
+            4   5    69  1379 8   137 136 1367  2
+            8   69   2   5    19  137 136 4     367
+        ");
+        let solution =
+            "523168974918473256674259813196735428287914635345826791731642589459387162862591347";
+
+        let found = cell_forcing(&bitmap);
+        assert_eq!(found[0].kind, ForcingKind::Cell);
+        let assumed: Vec<Candidate> = found[0].branches.iter().map(|b| b.assumption).collect();
+        assert_eq!(assumed, vec![
+            Candidate { point: point(0, 0), digit: digit::FIVE },
+            Candidate { point: point(0, 0), digit: digit::SEVEN },
+            Candidate { point: point(0, 0), digit: digit::NINE },
+        ]);
+        assert!(found[0].branches[2].contradiction.is_some());
+        assert_eq!(found[0].eliminations, vec![assumed[2]]);
+        let solution_digits: Vec<char> = solution.chars().collect();
+        for forcing in found.iter().chain(&unit_forcing(&bitmap)).chain(&nishio(&bitmap)) {
+            assert_sound(solution, &forcing.eliminations);
+            for c in &forcing.placements {
+                let index: usize = c.point.into();
+                assert_eq!(solution_digits[index], char::from(c.digit));
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use crate::bitmap::Bitmap;
+use crate::digit::Digit;
+use crate::grid;
+use crate::grid::{GridPoint, GridUnit};
+use crate::technique::{digit_points, outcome, Candidate, Step, Technique};
+use crate::variants;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum IntersectionKind {
+    /// The digit's cells in a block all lie on one line.
+    Pointing,
+    /// The digit's cells on a line all lie in one block.
+    Claiming,
+}
+
+/// Locked candidates: the digit must go where `base` and `cover` overlap, so
+/// it leaves the rest of `cover`.
+#[derive(Clone, Debug)]
+pub struct Intersection {
+    pub kind: IntersectionKind,
+    pub digit: Digit,
+    pub base: GridUnit,
+    pub cover: GridUnit,
+    pub points: Vec<GridPoint>,
+    pub eliminations: Vec<Candidate>,
+}
+
+pub fn intersections(bitmap: &Bitmap) -> Vec<Intersection> {
+    let mut result = Vec::new();
+    for base in grid::units() {
+        let kind = match base {
+            GridUnit::Block(_) => IntersectionKind::Pointing,
+            _ => IntersectionKind::Claiming,
+        };
+        for digit in variants::ANY {
+            let points = digit_points(bitmap, base, digit);
+            if points.len() < 2 {
+                continue;
+            }
+            for cover in covers(base, points[0]) {
+                let overlap = base.intersection(&cover);
+                if !points.iter().all(|p| overlap.contains(p)) {
+                    continue;
+                }
+                let eliminations: Vec<Candidate> = digit_points(bitmap, cover, digit)
+                    .into_iter()
+                    .filter(|&p| !base.contains(p))
+                    .map(|point| Candidate { point, digit })
+                    .collect();
+                if !eliminations.is_empty() {
+                    result.push(Intersection {
+                        kind,
+                        digit,
+                        base,
+                        cover,
+                        points: points.clone(),
+                        eliminations,
+                    });
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Units crossing `base` at `p` that can hold a locked candidate.
+fn covers(base: GridUnit, p: GridPoint) -> Vec<GridUnit> {
+    match base {
+        GridUnit::Block(_) => vec![GridUnit::Row(p.row()), GridUnit::Column(p.column())],
+        _ => vec![GridUnit::Block(p.block())],
+    }
+}
+
+impl Intersection {
+    pub fn name(&self) -> &'static str {
+        match self.kind {
+            IntersectionKind::Pointing => "Pointing",
+            IntersectionKind::Claiming => "Claiming",
+        }
+    }
+
+    pub fn step(&self) -> Step {
+        Step {
+            name: self.name(),
+            placements: Vec::new(),
+            eliminations: self.eliminations.clone(),
+            units: vec![self.base, self.cover],
+            cells: self.points.clone(),
+            candidates: self
+                .points
+                .iter()
+                .map(|&point| Candidate {
+                    point,
+                    digit: self.digit,
+                })
+                .collect(),
+            explanation: format!(
+                "in {}, {} lies only in {}, so {}",
+                self.base,
+                char::from(self.digit),
+                self.cover,
+                outcome(&[], &self.eliminations)
+            ),
+        }
+    }
+}
+
+/// Pointing and claiming together.
+pub struct LockedCandidates;
+
+impl Technique for LockedCandidates {
+    fn name(&self) -> &'static str {
+        "Locked Candidates"
+    }
+
+    fn find(&self, bitmap: &Bitmap) -> Option<Step> {
+        intersections(bitmap).first().map(Intersection::step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bitmap::Bitmap;
+    use crate::digit;
+    use crate::grid::{GridBlock, GridColumn, GridPoint, GridRow, GridUnit};
+    use crate::technique::intersections::{intersections, IntersectionKind};
+
+    #[test]
+    fn test_pointing() {
+        let mut bitmap = Bitmap::new();
+        let block = GridUnit::Block(GridBlock::from(0));
+        for p in block.points() {
+            if p.row() != GridRow(0) {
+                bitmap.remove_variant(p, digit::FIVE);
+            }
+        }
+
+        let found = intersections(&bitmap);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].kind, IntersectionKind::Pointing);
+        assert_eq!(found[0].base, block);
+        assert_eq!(found[0].cover, GridUnit::Row(GridRow(0)));
+        assert_eq!(found[0].eliminations.len(), 6);
+        assert!(found[0].eliminations.iter().all(|c| c.point.column().0 >= 3));
+    }
+
+    #[test]
+    fn test_claiming() {
+        let mut bitmap = Bitmap::new();
+        let row = GridUnit::Row(GridRow(4));
+        for p in row.points() {
+            if p.column().0 >= 3 {
+                bitmap.remove_variant(p, digit::TWO);
+            }
+        }
+
+        let found = intersections(&bitmap);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].kind, IntersectionKind::Claiming);
+        assert_eq!(found[0].base, row);
+        assert_eq!(found[0].cover, GridUnit::Block(GridBlock::from(3)));
+        assert_eq!(found[0].eliminations.len(), 6);
+        let p = GridPoint { x: GridColumn(1), y: GridRow(3) };
+        assert!(found[0].eliminations.iter().any(|c| c.point == p));
+    }
+}
@@ -0,0 +1,247 @@
+pub mod als;
+pub mod chains;
+pub mod coloring;
+pub mod fish;
+pub mod forcing;
+pub mod intersections;
+pub mod singles;
+pub mod subsets;
+pub mod uniqueness;
+pub mod wings;
+
+use crate::bitmap::Bitmap;
+use crate::digit::Digit;
+use crate::grid::{GridPoint, GridUnit};
+use crate::variants::Variants;
+use std::{cmp, fmt};
+
+/// A single digit in a single cell, the unit most deductions talk about.
+#[derive(Copy, Clone, Debug)]
+pub struct Candidate {
+    pub point: GridPoint,
+    pub digit: Digit,
+}
+
+impl cmp::PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.point == other.point && self.digit == other.digit
+    }
+}
+
+/// Eureka notation, e.g. `(7)r3c5`.
+impl fmt::Display for Candidate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}){}", char::from(self.digit), self.point)
+    }
+}
+
+/// One deduction found on a grid: what it changes and what it rests on.
+#[derive(Clone, Debug)]
+pub struct Step {
+    /// Name of the pattern, e.g. "Hidden Single" or "Finned X-Wing".
+    pub name: &'static str,
+    pub placements: Vec<Candidate>,
+    pub eliminations: Vec<Candidate>,
+    /// Units the pattern is built on.
+    pub units: Vec<GridUnit>,
+    /// Cells the pattern is built on.
+    pub cells: Vec<GridPoint>,
+    /// Candidates the pattern is built on.
+    pub candidates: Vec<Candidate>,
+    /// What the pattern is and what follows from it, in plain words.
+    pub explanation: String,
+}
+
+/// Renders as "Hidden Single: 7 in row 3 must go in r3c5".
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.explanation)
+    }
+}
+
+impl Step {
+    /// Places and removes the step's candidates, returning whether the bitmap
+    /// changed.
+    pub fn apply(&self, bitmap: &mut Bitmap) -> bool {
+        let before = bitmap.count_ones();
+        for c in &self.placements {
+            bitmap.set_known_digit(c.point, c.digit);
+        }
+        for c in &self.eliminations {
+            bitmap.remove_variant(c.point, c.digit);
+        }
+        bitmap.count_ones() != before
+    }
+}
+
+/// A way of finding deductions. Implement it to plug a technique of your own
+/// into a `Strategy`.
+pub trait Technique {
+    /// Identifies the technique within a strategy.
+    fn name(&self) -> &'static str;
+
+    /// The first deduction the technique can make, if any. A step that does
+    /// not change the bitmap is passed over in favour of the next technique.
+    fn find(&self, bitmap: &Bitmap) -> Option<Step>;
+
+    /// Difficulty of one of the technique's steps on the rating scale. By
+    /// default the rating knows the built-in step names, and rates any other
+    /// step above them all.
+    fn difficulty(&self, _step: &Step) -> Option<f32> {
+        None
+    }
+}
+
+/// Joins anything displayable with commas, e.g. "r1c1, r1c5".
+pub(crate) fn list<T: fmt::Display>(items: impl IntoIterator<Item = T>) -> String {
+    items
+        .into_iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub(crate) fn digits(variants: Variants) -> String {
+    list(variants.into_iter().map(char::from))
+}
+
+/// What a step changes, e.g. "place 7 in r3c5 and remove 4 from r1c1, r1c2".
+pub(crate) fn outcome(placements: &[Candidate], eliminations: &[Candidate]) -> String {
+    let mut parts: Vec<String> = placements
+        .iter()
+        .map(|c| format!("place {} in {}", char::from(c.digit), c.point))
+        .collect();
+    for digit in crate::variants::ANY {
+        let points: Vec<GridPoint> = eliminations
+            .iter()
+            .filter(|c| c.digit == digit)
+            .map(|c| c.point)
+            .collect();
+        if !points.is_empty() {
+            parts.push(format!("remove {} from {}", char::from(digit), list(points)));
+        }
+    }
+    parts.join(" and ")
+}
+
+/// Cells of the unit that still have the digit among their variants.
+pub(crate) fn digit_points(bitmap: &Bitmap, unit: GridUnit, digit: Digit) -> Vec<GridPoint> {
+    unit.points()
+        .iter()
+        .copied()
+        .filter(|&p| bitmap.has_variant(p, digit))
+        .collect()
+}
+
+/// Bit `i` is set when the `i`-th cell of the unit still has the digit.
+pub(crate) fn digit_mask(bitmap: &Bitmap, unit: GridUnit, digit: Digit) -> u16 {
+    unit.points()
+        .iter()
+        .enumerate()
+        .filter(|(_, &p)| bitmap.has_variant(p, digit))
+        .fold(0, |mask, (i, _)| mask | 1 << i)
+}
+
+/// Candidates of the digit in cells that see every one of `points`.
+pub(crate) fn common_peer_eliminations(
+    bitmap: &Bitmap,
+    digit: Digit,
+    points: &[GridPoint],
+) -> Vec<Candidate> {
+    points[0]
+        .peers()
+        .into_iter()
+        .filter(|p| points.iter().all(|q| q.sees(p)))
+        .filter(|&p| bitmap.has_variant(p, digit))
+        .map(|point| Candidate { point, digit })
+        .collect()
+}
+
+/// Every candidate of the digits left in the cells.
+pub(crate) fn candidates_in(bitmap: &Bitmap, cells: &[GridPoint], digits: Variants) -> Vec<Candidate> {
+    cells
+        .iter()
+        .flat_map(|&point| {
+            (bitmap.get_variants(point) & digits)
+                .into_iter()
+                .map(move |digit| Candidate { point, digit })
+        })
+        .collect()
+}
+
+pub(crate) fn is_solved(variants: Variants) -> bool {
+    variants.count() < 2
+}
+
+/// Every way to pick `k` of the indices `0..n`, in lexicographic order.
+pub(crate) fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    fn extend(start: usize, n: usize, k: usize, current: &mut Vec<usize>, out: &mut Vec<Vec<usize>>) {
+        if current.len() == k {
+            out.push(current.clone());
+            return;
+        }
+        for i in start..n {
+            current.push(i);
+            extend(i + 1, n, k, current, out);
+            current.pop();
+        }
+    }
+    let mut out = Vec::new();
+    extend(0, n, k, &mut Vec::with_capacity(k), &mut out);
+    out
+}
+
+/// Cells and candidates the technique tests build their boards from.
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use crate::bitmap::Bitmap;
+    use crate::digit::Digit;
+    use crate::grid;
+    use crate::grid::GridPoint;
+    use crate::technique::Candidate;
+    use crate::variants::Variants;
+    use std::convert::TryFrom;
+
+    pub(crate) use crate::grid::point;
+
+    pub fn variants(digits: &[Digit]) -> Variants {
+        digits.iter().fold(Variants::new(), |acc, &d| acc | d)
+    }
+
+    pub fn pair(a: Digit, b: Digit) -> Variants {
+        Variants::new() | a | b
+    }
+
+    /// A bitmap from the candidates of every cell, row by row and separated
+    /// by whitespace, the way pencilmarks are usually written down.
+    pub fn pencilmarks(text: &str) -> Bitmap {
+        let cells: Vec<&str> = text.split_whitespace().collect();
+        assert_eq!(cells.len(), grid::WIDTH * grid::HEIGHT);
+        let mut bitmap = Bitmap::new();
+        for (p, cell) in grid::POINTS.into_iter().zip(cells) {
+            let digits: Vec<Digit> = cell.chars().map(|ch| Digit::try_from(ch).unwrap()).collect();
+            bitmap.set_variants(p, variants(&digits));
+        }
+        bitmap
+    }
+
+    /// Fails on an elimination of the digit the solution puts in its cell.
+    pub fn assert_sound(solution: &str, eliminations: &[Candidate]) {
+        let solution: Vec<char> = solution.chars().collect();
+        for c in eliminations {
+            let index: usize = c.point.into();
+            assert_ne!(solution[index], char::from(c.digit), "{:?} is solved by it", c.point);
+        }
+    }
+
+    /// A bitmap where only the given cells may hold the digit.
+    pub fn only_at(digit: Digit, points: &[GridPoint]) -> Bitmap {
+        let mut bitmap = Bitmap::new();
+        for p in grid::POINTS {
+            if !points.contains(&p) {
+                bitmap.remove_variant(p, digit);
+            }
+        }
+        bitmap
+    }
+}
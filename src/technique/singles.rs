@@ -0,0 +1,163 @@
+use crate::bitmap::Bitmap;
+use crate::grid;
+use crate::grid::GridUnit;
+use crate::technique::{digit_points, is_solved, Candidate, Step, Technique};
+use crate::variants;
+
+/// A cell left with a single digit that its peers still hold as a variant.
+pub fn naked_singles(bitmap: &Bitmap) -> Vec<Candidate> {
+    let mut result = Vec::new();
+    for point in grid::POINTS {
+        let variants = bitmap.get_variants(point);
+        if variants.count() != 1 {
+            continue;
+        }
+        let digit = variants.into_iter().next().unwrap();
+        if point.peers().iter().any(|&p| bitmap.has_variant(p, digit)) {
+            result.push(Candidate { point, digit });
+        }
+    }
+    result
+}
+
+pub struct NakedSingles;
+
+impl Technique for NakedSingles {
+    fn name(&self) -> &'static str {
+        "Naked Single"
+    }
+
+    fn find(&self, bitmap: &Bitmap) -> Option<Step> {
+        naked_singles(bitmap).first().copied().map(naked_single)
+    }
+}
+
+pub fn naked_single(candidate: Candidate) -> Step {
+    Step {
+        name: "Naked Single",
+        placements: vec![candidate],
+        eliminations: Vec::new(),
+        units: Vec::new(),
+        cells: vec![candidate.point],
+        candidates: vec![candidate],
+        explanation: format!(
+            "{} can only be {}",
+            candidate.point,
+            char::from(candidate.digit)
+        ),
+    }
+}
+
+/// A digit that fits in only one cell of a unit.
+#[derive(Copy, Clone, Debug)]
+pub struct HiddenSingle {
+    pub unit: GridUnit,
+    pub candidate: Candidate,
+}
+
+pub fn hidden_singles(bitmap: &Bitmap) -> Vec<HiddenSingle> {
+    let mut result = Vec::new();
+    for unit in grid::units() {
+        for digit in variants::ANY {
+            let points = digit_points(bitmap, unit, digit);
+            if points.len() != 1 || is_solved(bitmap.get_variants(points[0])) {
+                continue;
+            }
+            result.push(HiddenSingle {
+                unit,
+                candidate: Candidate {
+                    point: points[0],
+                    digit,
+                },
+            });
+        }
+    }
+    result
+}
+
+impl HiddenSingle {
+    pub fn step(&self) -> Step {
+        Step {
+            name: "Hidden Single",
+            placements: vec![self.candidate],
+            eliminations: Vec::new(),
+            units: vec![self.unit],
+            cells: vec![self.candidate.point],
+            candidates: vec![self.candidate],
+            explanation: format!(
+                "{} in {} must go in {}",
+                char::from(self.candidate.digit),
+                self.unit,
+                self.candidate.point
+            ),
+        }
+    }
+}
+
+pub struct HiddenSingles;
+
+impl Technique for HiddenSingles {
+    fn name(&self) -> &'static str {
+        "Hidden Single"
+    }
+
+    /// Prefers a single in a block, the easiest kind to spot.
+    fn find(&self, bitmap: &Bitmap) -> Option<Step> {
+        hidden_singles(bitmap)
+            .iter()
+            .min_by_key(|single| !matches!(single.unit, GridUnit::Block(_)))
+            .map(HiddenSingle::step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bitmap::Bitmap;
+    use crate::digit;
+    use crate::grid::{GridColumn, GridPoint, GridRow, GridUnit};
+    use crate::technique::singles::{hidden_singles, naked_singles, HiddenSingles, NakedSingles};
+    use crate::technique::Technique;
+
+    #[test]
+    fn test_hidden_single_in_row() {
+        let mut bitmap = Bitmap::new();
+        let target = GridPoint {
+            x: GridColumn(4),
+            y: GridRow(2),
+        };
+        for p in GridUnit::Row(GridRow(2)).points() {
+            if p != target {
+                bitmap.remove_variant(p, digit::SEVEN);
+            }
+        }
+
+        let singles = hidden_singles(&bitmap);
+        assert_eq!(singles.len(), 1);
+        assert_eq!(singles[0].unit, GridUnit::Row(GridRow(2)));
+        assert_eq!(singles[0].candidate.point, target);
+        assert_eq!(singles[0].candidate.digit, digit::SEVEN);
+
+        let step = HiddenSingles.find(&bitmap).unwrap();
+        assert_eq!(step.units, vec![GridUnit::Row(GridRow(2))]);
+        assert_eq!(step.to_string(), "Hidden Single: 7 in row 3 must go in r3c5");
+        assert!(step.apply(&mut bitmap));
+        assert_eq!(bitmap.get_digit(target), Ok(digit::SEVEN));
+        assert!(!bitmap.has_variant(GridPoint { x: GridColumn(4), y: GridRow(7) }, digit::SEVEN));
+    }
+
+    #[test]
+    fn test_naked_single() {
+        let mut bitmap = Bitmap::new();
+        let target = GridPoint {
+            x: GridColumn(1),
+            y: GridRow(5),
+        };
+        bitmap.set_variants(target, digit::FOUR.into());
+        assert_eq!(naked_singles(&bitmap).len(), 1);
+
+        let step = NakedSingles.find(&bitmap).unwrap();
+        assert_eq!(step.placements[0].digit, digit::FOUR);
+        assert!(step.apply(&mut bitmap));
+        assert!(NakedSingles.find(&bitmap).is_none());
+    }
+}
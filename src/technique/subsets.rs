@@ -0,0 +1,246 @@
+use crate::bitmap::Bitmap;
+use crate::grid;
+use crate::grid::{GridPoint, GridUnit};
+use crate::technique::{
+    candidates_in, combinations, digit_points, digits, is_solved, list, outcome, Candidate, Step,
+    Technique,
+};
+use crate::variants;
+use crate::variants::Variants;
+
+pub const MIN_SIZE: usize = 2;
+pub const MAX_SIZE: usize = 4;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SubsetKind {
+    Naked,
+    Hidden,
+}
+
+/// `points.len()` cells of a unit locked to the same number of `digits`.
+#[derive(Clone, Debug)]
+pub struct Subset {
+    pub kind: SubsetKind,
+    pub unit: GridUnit,
+    pub points: Vec<GridPoint>,
+    pub digits: Variants,
+    pub eliminations: Vec<Candidate>,
+}
+
+/// Cells whose variants together hold exactly `size` digits. Those digits
+/// can go nowhere else in the unit.
+pub fn naked_subsets(bitmap: &Bitmap, size: usize) -> Vec<Subset> {
+    let mut result = Vec::new();
+    for unit in grid::units() {
+        let open: Vec<GridPoint> = unit
+            .points()
+            .iter()
+            .copied()
+            .filter(|&p| !is_solved(bitmap.get_variants(p)))
+            .collect();
+        for picked in combinations(open.len(), size) {
+            let digits = picked
+                .iter()
+                .fold(Variants::new(), |acc, &i| acc | bitmap.get_variants(open[i]));
+            if digits.count() as usize != size {
+                continue;
+            }
+            let points: Vec<GridPoint> = picked.iter().map(|&i| open[i]).collect();
+            let eliminations: Vec<Candidate> = open
+                .iter()
+                .filter(|p| !points.contains(p))
+                .flat_map(|&point| {
+                    (bitmap.get_variants(point) & digits)
+                        .into_iter()
+                        .map(move |digit| Candidate { point, digit })
+                })
+                .collect();
+            if !eliminations.is_empty() {
+                result.push(Subset {
+                    kind: SubsetKind::Naked,
+                    unit,
+                    points,
+                    digits,
+                    eliminations,
+                });
+            }
+        }
+    }
+    result
+}
+
+/// Digits that fit in only `size` cells of a unit. Those cells can hold
+/// nothing else.
+pub fn hidden_subsets(bitmap: &Bitmap, size: usize) -> Vec<Subset> {
+    let mut result = Vec::new();
+    for unit in grid::units() {
+        let mut digits = Vec::new();
+        for digit in variants::ANY {
+            let points = digit_points(bitmap, unit, digit);
+            if points.len() >= MIN_SIZE && points.len() <= size {
+                digits.push((digit, points));
+            }
+        }
+        for picked in combinations(digits.len(), size) {
+            let mut points: Vec<GridPoint> = Vec::new();
+            for &i in &picked {
+                for &p in &digits[i].1 {
+                    if !points.contains(&p) {
+                        points.push(p);
+                    }
+                }
+            }
+            if points.len() != size {
+                continue;
+            }
+            let subset = picked
+                .iter()
+                .fold(Variants::new(), |acc, &i| acc | digits[i].0);
+            let eliminations: Vec<Candidate> = points
+                .iter()
+                .flat_map(|&point| {
+                    (bitmap.get_variants(point) & !subset)
+                        .into_iter()
+                        .map(move |digit| Candidate { point, digit })
+                })
+                .collect();
+            if !eliminations.is_empty() {
+                points.sort_by_key(|&p| Into::<usize>::into(p));
+                result.push(Subset {
+                    kind: SubsetKind::Hidden,
+                    unit,
+                    points,
+                    digits: subset,
+                    eliminations,
+                });
+            }
+        }
+    }
+    result
+}
+
+impl Subset {
+    pub fn name(&self) -> &'static str {
+        match (self.kind, self.points.len()) {
+            (SubsetKind::Naked, 2) => "Naked Pair",
+            (SubsetKind::Naked, 3) => "Naked Triple",
+            (SubsetKind::Naked, _) => "Naked Quad",
+            (SubsetKind::Hidden, 2) => "Hidden Pair",
+            (SubsetKind::Hidden, 3) => "Hidden Triple",
+            (SubsetKind::Hidden, _) => "Hidden Quad",
+        }
+    }
+
+    pub fn step(&self, bitmap: &Bitmap) -> Step {
+        Step {
+            name: self.name(),
+            placements: Vec::new(),
+            eliminations: self.eliminations.clone(),
+            units: vec![self.unit],
+            cells: self.points.clone(),
+            candidates: candidates_in(bitmap, &self.points, self.digits),
+            explanation: format!(
+                "{}, so {}",
+                self.pattern(),
+                outcome(&[], &self.eliminations)
+            ),
+        }
+    }
+
+    fn pattern(&self) -> String {
+        let (points, digits) = (list(self.points.iter()), digits(self.digits));
+        match self.kind {
+            SubsetKind::Naked => format!("{} hold only {} within {}", points, digits, self.unit),
+            SubsetKind::Hidden => format!("{} fit only in {} within {}", digits, points, self.unit),
+        }
+    }
+}
+
+/// Naked subsets of one size, so that pairs can be ranked apart from quads.
+pub struct NakedSubsets {
+    pub size: usize,
+}
+
+impl Technique for NakedSubsets {
+    fn name(&self) -> &'static str {
+        match self.size {
+            2 => "Naked Pair",
+            3 => "Naked Triple",
+            _ => "Naked Quad",
+        }
+    }
+
+    fn find(&self, bitmap: &Bitmap) -> Option<Step> {
+        naked_subsets(bitmap, self.size)
+            .first()
+            .map(|s| s.step(bitmap))
+    }
+}
+
+pub struct HiddenSubsets {
+    pub size: usize,
+}
+
+impl Technique for HiddenSubsets {
+    fn name(&self) -> &'static str {
+        match self.size {
+            2 => "Hidden Pair",
+            3 => "Hidden Triple",
+            _ => "Hidden Quad",
+        }
+    }
+
+    fn find(&self, bitmap: &Bitmap) -> Option<Step> {
+        hidden_subsets(bitmap, self.size)
+            .first()
+            .map(|s| s.step(bitmap))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bitmap::Bitmap;
+    use crate::digit;
+    use crate::grid::{GridColumn, GridPoint, GridRow, GridUnit};
+    use crate::technique::subsets::{hidden_subsets, naked_subsets, SubsetKind};
+    use crate::variants::Variants;
+
+    #[test]
+    fn test_naked_pair() {
+        let mut bitmap = Bitmap::new();
+        let pair = Variants::new() | digit::THREE | digit::EIGHT;
+        let a = GridPoint { x: GridColumn(0), y: GridRow(0) };
+        let b = GridPoint { x: GridColumn(0), y: GridRow(5) };
+        bitmap.set_variants(a, pair);
+        bitmap.set_variants(b, pair);
+
+        let found = naked_subsets(&bitmap, 2);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].kind, SubsetKind::Naked);
+        assert_eq!(found[0].unit, GridUnit::Column(GridColumn(0)));
+        assert_eq!(found[0].points, vec![a, b]);
+        assert_eq!(found[0].digits, pair);
+        assert_eq!(found[0].eliminations.len(), 7 * 2);
+        assert!(naked_subsets(&bitmap, 3).is_empty());
+    }
+
+    #[test]
+    fn test_hidden_pair() {
+        let mut bitmap = Bitmap::new();
+        let a = GridPoint { x: GridColumn(2), y: GridRow(4) };
+        let b = GridPoint { x: GridColumn(7), y: GridRow(4) };
+        for p in GridUnit::Row(GridRow(4)).points() {
+            if p != a && p != b {
+                bitmap.remove_variant(p, digit::ONE);
+                bitmap.remove_variant(p, digit::SIX);
+            }
+        }
+
+        let found = hidden_subsets(&bitmap, 2);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].kind, SubsetKind::Hidden);
+        assert_eq!(found[0].points, vec![a, b]);
+        assert_eq!(found[0].digits, Variants::new() | digit::ONE | digit::SIX);
+        assert_eq!(found[0].eliminations.len(), 7 * 2);
+    }
+}
@@ -0,0 +1,574 @@
+use crate::bitmap::Bitmap;
+use crate::grid;
+use crate::grid::{GridColumn, GridPoint, GridRow, GridUnit};
+use crate::technique::{
+    candidates_in, combinations, common_peer_eliminations, digit_points, digits, is_solved, list,
+    outcome, Candidate, Step, Technique,
+};
+use crate::variants;
+use crate::variants::Variants;
+use std::convert::TryInto;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum UniquenessKind {
+    Type1,
+    Type2,
+    Type3,
+    Type4,
+    Type5,
+    Type6,
+    HiddenRectangle,
+    BugPlusOne,
+}
+
+/// A deduction that avoids a deadly pattern, a candidate layout with two
+/// interchangeable solutions. Sound only for puzzles with a single solution.
+#[derive(Clone, Debug)]
+pub struct Uniqueness {
+    pub kind: UniquenessKind,
+    pub points: Vec<GridPoint>,
+    pub digits: Variants,
+    pub placements: Vec<Candidate>,
+    pub eliminations: Vec<Candidate>,
+}
+
+impl Uniqueness {
+    pub fn name(&self) -> &'static str {
+        match self.kind {
+            UniquenessKind::Type1 => "Unique Rectangle Type 1",
+            UniquenessKind::Type2 => "Unique Rectangle Type 2",
+            UniquenessKind::Type3 => "Unique Rectangle Type 3",
+            UniquenessKind::Type4 => "Unique Rectangle Type 4",
+            UniquenessKind::Type5 => "Unique Rectangle Type 5",
+            UniquenessKind::Type6 => "Unique Rectangle Type 6",
+            UniquenessKind::HiddenRectangle => "Hidden Rectangle",
+            UniquenessKind::BugPlusOne => "BUG+1",
+        }
+    }
+
+    pub fn step(&self, bitmap: &Bitmap) -> Step {
+        Step {
+            name: self.name(),
+            placements: self.placements.clone(),
+            eliminations: self.eliminations.clone(),
+            units: Vec::new(),
+            cells: self.points.clone(),
+            candidates: candidates_in(bitmap, &self.points, self.digits),
+            explanation: self.explanation(),
+        }
+    }
+
+    fn explanation(&self) -> String {
+        let pattern = match self.kind {
+            UniquenessKind::BugPlusOne => format!(
+                "every other cell has two candidates and every digit two places in each unit, \
+                 so {} is the only way out",
+                self.points[0]
+            ),
+            _ => format!(
+                "{} would form a deadly pattern on {}",
+                list(self.points.iter()),
+                digits(self.digits)
+            ),
+        };
+        format!(
+            "{}, so {}",
+            pattern,
+            outcome(&self.placements, &self.eliminations)
+        )
+    }
+}
+
+/// Four unsolved cells on two rows, two columns and two blocks that all
+/// still hold both `digits`. Corners go row by row, so `3 - i` is the corner
+/// diagonal to `i`.
+struct Rectangle {
+    corners: [GridPoint; 4],
+    digits: Variants,
+}
+
+impl Rectangle {
+    fn shares_line(&self, i: usize, j: usize) -> bool {
+        let (a, b) = (self.corners[i], self.corners[j]);
+        a.row() == b.row() || a.column() == b.column()
+    }
+
+    fn contains(&self, p: GridPoint) -> bool {
+        self.corners.contains(&p)
+    }
+
+    fn report(&self, kind: UniquenessKind, eliminations: Vec<Candidate>) -> Option<Uniqueness> {
+        if eliminations.is_empty() {
+            return None;
+        }
+        Some(Uniqueness {
+            kind,
+            points: self.corners.to_vec(),
+            digits: self.digits,
+            placements: Vec::new(),
+            eliminations,
+        })
+    }
+}
+
+fn rectangles(bitmap: &Bitmap) -> Vec<Rectangle> {
+    let mut result = Vec::new();
+    for rows in combinations(grid::HEIGHT, 2) {
+        for columns in combinations(grid::WIDTH, 2) {
+            let same_band = rows[0] / grid::BLOCK_SIZE == rows[1] / grid::BLOCK_SIZE;
+            let same_stack = columns[0] / grid::BLOCK_SIZE == columns[1] / grid::BLOCK_SIZE;
+            if same_band == same_stack {
+                continue;
+            }
+            let corners = [0, 1, 2, 3].map(|i| GridPoint {
+                x: GridColumn(columns[i % 2]),
+                y: GridRow(rows[i / 2]),
+            });
+            if corners.iter().any(|&p| is_solved(bitmap.get_variants(p))) {
+                continue;
+            }
+            let common = corners
+                .iter()
+                .fold(!Variants::new(), |acc, &p| acc & bitmap.get_variants(p));
+            let common: Vec<_> = common.into_iter().collect();
+            for pair in combinations(common.len(), 2) {
+                result.push(Rectangle {
+                    corners,
+                    digits: Variants::new() | common[pair[0]] | common[pair[1]],
+                });
+            }
+        }
+    }
+    result
+}
+
+/// Units holding both cells.
+fn shared_units(a: GridPoint, b: GridPoint) -> Vec<GridUnit> {
+    [
+        GridUnit::Row(a.row()),
+        GridUnit::Column(a.column()),
+        GridUnit::Block(a.block()),
+    ]
+    .into_iter()
+    .filter(|unit| unit.contains(b))
+    .collect()
+}
+
+fn to_candidates(point: GridPoint, digits: Variants) -> Vec<Candidate> {
+    digits
+        .into_iter()
+        .map(|digit| Candidate { point, digit })
+        .collect()
+}
+
+pub fn unique_rectangles(bitmap: &Bitmap) -> Vec<Uniqueness> {
+    let mut result = Vec::new();
+    for rect in rectangles(bitmap) {
+        let pair = rect.digits;
+        let variants = rect.corners.map(|p| bitmap.get_variants(p));
+        let floors: Vec<usize> = (0..4).filter(|&i| variants[i] == pair).collect();
+        let roofs: Vec<usize> = (0..4).filter(|&i| variants[i] != pair).collect();
+        if floors.is_empty() {
+            continue;
+        }
+        let mut found: Vec<Option<Uniqueness>> = Vec::new();
+
+        if floors.len() == 3 {
+            let roof = rect.corners[roofs[0]];
+            found.push(rect.report(UniquenessKind::Type1, to_candidates(roof, pair)));
+        }
+
+        let extras: Vec<Variants> = roofs.iter().map(|&i| variants[i] & !pair).collect();
+        if roofs.len() >= 2 && extras.iter().all(|&e| e.count() == 1 && e == extras[0]) {
+            let digit = extras[0].try_into().unwrap();
+            let points: Vec<GridPoint> = roofs.iter().map(|&i| rect.corners[i]).collect();
+            let kind = if roofs.len() == 2 && rect.shares_line(roofs[0], roofs[1]) {
+                UniquenessKind::Type2
+            } else {
+                UniquenessKind::Type5
+            };
+            found.push(rect.report(kind, common_peer_eliminations(bitmap, digit, &points)));
+        }
+
+        if roofs.len() == 2 && rect.shares_line(roofs[0], roofs[1]) {
+            let (a, b) = (rect.corners[roofs[0]], rect.corners[roofs[1]]);
+            let extra = extras[0] | extras[1];
+            for unit in shared_units(a, b) {
+                found.extend(type3(bitmap, &rect, unit, extra));
+                for x in pair {
+                    if digit_points(bitmap, unit, x) == vec![a, b] {
+                        let y = pair ^ x;
+                        let eliminations = [to_candidates(a, y), to_candidates(b, y)].concat();
+                        found.push(rect.report(UniquenessKind::Type4, eliminations));
+                    }
+                }
+            }
+        }
+
+        if roofs.len() == 2 && !rect.shares_line(roofs[0], roofs[1]) {
+            for x in pair {
+                let locked = |units: [GridUnit; 2]| {
+                    units.iter().all(|&unit| {
+                        digit_points(bitmap, unit, x)
+                            .iter()
+                            .all(|&p| rect.contains(p))
+                    })
+                };
+                let (first, last) = (rect.corners[0], rect.corners[3]);
+                let rows = [GridUnit::Row(first.row()), GridUnit::Row(last.row())];
+                let columns = [GridUnit::Column(first.column()), GridUnit::Column(last.column())];
+                if locked(rows) || locked(columns) {
+                    let eliminations = roofs
+                        .iter()
+                        .map(|&i| Candidate { point: rect.corners[i], digit: x })
+                        .collect();
+                    found.push(rect.report(UniquenessKind::Type6, eliminations));
+                }
+            }
+        }
+
+        for &floor in &floors {
+            let diagonal = 3 - floor;
+            if variants[diagonal] == pair {
+                continue;
+            }
+            let d = rect.corners[diagonal];
+            for x in pair {
+                let locked = [GridUnit::Row(d.row()), GridUnit::Column(d.column())]
+                    .iter()
+                    .all(|&unit| digit_points(bitmap, unit, x).iter().all(|&p| rect.contains(p)));
+                if locked {
+                    let eliminations = to_candidates(d, pair ^ x);
+                    found.push(rect.report(UniquenessKind::HiddenRectangle, eliminations));
+                }
+            }
+        }
+
+        result.extend(found.into_iter().flatten());
+    }
+    result
+}
+
+/// The two roof cells act as one cell holding their extra digits, which may
+/// form a naked subset with other cells of a unit they share.
+fn type3(
+    bitmap: &Bitmap,
+    rect: &Rectangle,
+    unit: GridUnit,
+    extra: Variants,
+) -> Vec<Option<Uniqueness>> {
+    let others: Vec<GridPoint> = unit
+        .points()
+        .iter()
+        .copied()
+        .filter(|&p| !rect.contains(p) && !is_solved(bitmap.get_variants(p)))
+        .collect();
+    let mut result = Vec::new();
+    for size in 1..=3 {
+        for picked in combinations(others.len(), size) {
+            let digits = picked
+                .iter()
+                .fold(extra, |acc, &i| acc | bitmap.get_variants(others[i]));
+            if digits.count() as usize != size + 1 {
+                continue;
+            }
+            let eliminations = others
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !picked.contains(i))
+                .flat_map(|(_, &p)| to_candidates(p, bitmap.get_variants(p) & digits))
+                .collect();
+            result.push(rect.report(UniquenessKind::Type3, eliminations));
+        }
+    }
+    result
+}
+
+/// Bivalue universal grave plus one: every unsolved cell holds two digits but
+/// one, which holds three. Without the odd digit of that cell each digit would
+/// appear twice or not at all among the unsolved cells of every unit, and the
+/// grid would have two solutions, so the digit is placed.
+pub fn bug_plus_one(bitmap: &Bitmap) -> Option<Uniqueness> {
+    let mut extra = None;
+    for p in grid::POINTS {
+        match bitmap.get_variants(p).count() {
+            1 | 2 => {}
+            3 if extra.is_none() => extra = Some(p),
+            _ => return None,
+        }
+    }
+    let point = extra?;
+    let odd = bitmap
+        .get_variants(point)
+        .into_iter()
+        .map(|digit| Candidate { point, digit })
+        .find(|&c| is_grave_without(bitmap, c))?;
+    Some(Uniqueness {
+        kind: UniquenessKind::BugPlusOne,
+        points: vec![point],
+        digits: bitmap.get_variants(point),
+        placements: vec![odd],
+        eliminations: Vec::new(),
+    })
+}
+
+/// Whether, once the candidate is taken out, every digit appears twice or
+/// not at all among the unsolved cells of every unit.
+fn is_grave_without(bitmap: &Bitmap, odd: Candidate) -> bool {
+    grid::units().all(|unit| {
+        variants::ANY.into_iter().all(|digit| {
+            let count = unit
+                .points()
+                .iter()
+                .filter(|&&p| !is_solved(bitmap.get_variants(p)))
+                .filter(|&&p| bitmap.has_variant(p, digit))
+                .filter(|&&p| p != odd.point || digit != odd.digit)
+                .count();
+            count == 0 || count == 2
+        })
+    })
+}
+
+/// Every unique rectangle type and hidden rectangles. Not part of the
+/// default strategy, as it assumes a single solution.
+pub struct UniqueRectangles;
+
+impl Technique for UniqueRectangles {
+    fn name(&self) -> &'static str {
+        "Unique Rectangle"
+    }
+
+    fn find(&self, bitmap: &Bitmap) -> Option<Step> {
+        unique_rectangles(bitmap).first().map(|u| u.step(bitmap))
+    }
+}
+
+/// Not part of the default strategy, as it assumes a single solution.
+pub struct BugPlusOne;
+
+impl Technique for BugPlusOne {
+    fn name(&self) -> &'static str {
+        "BUG+1"
+    }
+
+    fn find(&self, bitmap: &Bitmap) -> Option<Step> {
+        bug_plus_one(bitmap).map(|u| u.step(bitmap))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bitmap::Bitmap;
+    use crate::digit;
+    use crate::grid;
+    use crate::grid::{GridRow, GridUnit};
+    use crate::technique::fixtures::{assert_sound, pair, pencilmarks, point};
+    use crate::technique::uniqueness::{bug_plus_one, unique_rectangles, UniquenessKind};
+    use crate::technique::Candidate;
+    use std::convert::TryFrom;
+
+    fn kinds(bitmap: &Bitmap) -> Vec<UniquenessKind> {
+        unique_rectangles(bitmap).iter().map(|u| u.kind).collect()
+    }
+
+    #[test]
+    fn test_type1() {
+        let mut bitmap = Bitmap::new();
+        for p in [point(0, 0), point(0, 3), point(1, 0)] {
+            bitmap.set_variants(p, pair(digit::ONE, digit::TWO));
+        }
+
+        let found = unique_rectangles(&bitmap);
+        assert_eq!(kinds(&bitmap), vec![UniquenessKind::Type1]);
+        assert_eq!(
+            found[0].eliminations,
+            vec![
+                Candidate { point: point(1, 3), digit: digit::ONE },
+                Candidate { point: point(1, 3), digit: digit::TWO },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_type2() {
+        let mut bitmap = Bitmap::new();
+        bitmap.set_variants(point(0, 0), pair(digit::ONE, digit::TWO));
+        bitmap.set_variants(point(0, 3), pair(digit::ONE, digit::TWO));
+        bitmap.set_variants(point(1, 0), pair(digit::ONE, digit::TWO) | digit::FIVE);
+        bitmap.set_variants(point(1, 3), pair(digit::ONE, digit::TWO) | digit::FIVE);
+
+        let found = unique_rectangles(&bitmap);
+        assert_eq!(kinds(&bitmap), vec![UniquenessKind::Type2]);
+        assert_eq!(found[0].eliminations.len(), 7);
+        assert!(found[0].eliminations.iter().all(|c| c.digit == digit::FIVE));
+    }
+
+    #[test]
+    fn test_type4() {
+        let mut bitmap = Bitmap::new();
+        bitmap.set_variants(point(0, 0), pair(digit::ONE, digit::TWO));
+        bitmap.set_variants(point(0, 3), pair(digit::ONE, digit::TWO));
+        for p in GridUnit::Row(GridRow(1)).points() {
+            if p != point(1, 0) && p != point(1, 3) {
+                bitmap.remove_variant(p, digit::ONE);
+            }
+        }
+
+        let found = unique_rectangles(&bitmap);
+        assert_eq!(kinds(&bitmap), vec![UniquenessKind::Type4]);
+        assert_eq!(
+            found[0].eliminations,
+            vec![
+                Candidate { point: point(1, 0), digit: digit::TWO },
+                Candidate { point: point(1, 3), digit: digit::TWO },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_type3() {
+        // the roofs act as one 3/4 cell, a naked pair with r2c7 in row 2
+        let mut bitmap = Bitmap::new();
+        bitmap.set_variants(point(0, 0), pair(digit::ONE, digit::TWO));
+        bitmap.set_variants(point(0, 3), pair(digit::ONE, digit::TWO));
+        bitmap.set_variants(point(1, 0), pair(digit::ONE, digit::TWO) | digit::THREE);
+        bitmap.set_variants(point(1, 3), pair(digit::ONE, digit::TWO) | digit::FOUR);
+        bitmap.set_variants(point(1, 6), pair(digit::THREE, digit::FOUR));
+
+        let found = unique_rectangles(&bitmap);
+        assert_eq!(kinds(&bitmap), vec![UniquenessKind::Type3]);
+        let expected: Vec<Candidate> = [1, 2, 4, 5, 7, 8]
+            .into_iter()
+            .flat_map(|x| {
+                [digit::THREE, digit::FOUR].map(|digit| Candidate { point: point(1, x), digit })
+            })
+            .collect();
+        assert_eq!(found[0].eliminations, expected);
+    }
+
+    #[test]
+    fn test_type5() {
+        let mut bitmap = Bitmap::new();
+        bitmap.set_variants(point(0, 0), pair(digit::ONE, digit::TWO));
+        bitmap.set_variants(point(1, 3), pair(digit::ONE, digit::TWO));
+        bitmap.set_variants(point(0, 3), pair(digit::ONE, digit::TWO) | digit::FIVE);
+        bitmap.set_variants(point(1, 0), pair(digit::ONE, digit::TWO) | digit::FIVE);
+
+        let found = unique_rectangles(&bitmap);
+        assert_eq!(kinds(&bitmap), vec![UniquenessKind::Type5]);
+        let mut eliminated: Vec<usize> =
+            found[0].eliminations.iter().map(|c| c.point.into()).collect();
+        eliminated.sort();
+        let expected: Vec<usize> =
+            [point(0, 1), point(0, 2), point(1, 4), point(1, 5)].map(|p| p.into()).to_vec();
+        assert_eq!(eliminated, expected);
+        assert!(found[0].eliminations.iter().all(|c| c.digit == digit::FIVE));
+    }
+
+    #[test]
+    fn test_type6() {
+        // 1 is locked to the corners in both rows, so it sits on the floors
+        let mut bitmap = Bitmap::new();
+        bitmap.set_variants(point(0, 0), pair(digit::ONE, digit::TWO));
+        bitmap.set_variants(point(1, 3), pair(digit::ONE, digit::TWO));
+        for y in 0..2 {
+            for p in GridUnit::Row(GridRow(y)).points() {
+                if ![point(0, 0), point(0, 3), point(1, 0), point(1, 3)].contains(&p) {
+                    bitmap.remove_variant(p, digit::ONE);
+                }
+            }
+        }
+
+        let found = unique_rectangles(&bitmap);
+        assert_eq!(kinds(&bitmap), vec![UniquenessKind::Type6]);
+        assert_eq!(
+            found[0].eliminations,
+            vec![
+                Candidate { point: point(0, 3), digit: digit::ONE },
+                Candidate { point: point(1, 0), digit: digit::ONE },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hidden_rectangle() {
+        // 1 is locked to the rectangle in row 2 and column 4, so r2c4 can't be 2
+        let mut bitmap = Bitmap::new();
+        bitmap.set_variants(point(0, 0), pair(digit::ONE, digit::TWO));
+        let unit_points = [
+            GridUnit::Row(GridRow(1)).points(),
+            GridUnit::Column(point(0, 3).column()).points(),
+        ];
+        for p in unit_points.concat() {
+            if ![point(0, 3), point(1, 0), point(1, 3)].contains(&p) {
+                bitmap.remove_variant(p, digit::ONE);
+            }
+        }
+
+        let found = unique_rectangles(&bitmap);
+        assert_eq!(kinds(&bitmap), vec![UniquenessKind::HiddenRectangle]);
+        assert_eq!(
+            found[0].eliminations,
+            vec![Candidate { point: point(1, 3), digit: digit::TWO }]
+        );
+    }
+
+    #[test]
+    fn test_bug_plus_one() {
+        // a solved grid with a deadly 2/3 rectangle left open in r1c3, r1c6,
+        // r2c3 and r2c6, plus a stray 7 in r1c3
+        let solution =
+            "812753649943682175675491283154237896369845721287169534521974368438526917796318452";
+        let mut bitmap = Bitmap::new();
+        for (p, ch) in grid::POINTS.into_iter().zip(solution.chars()) {
+            bitmap.set_digit(p, digit::Digit::try_from(ch).unwrap());
+        }
+        let deadly = pair(digit::TWO, digit::THREE);
+        for p in [point(0, 2), point(0, 5), point(1, 2), point(1, 5)] {
+            bitmap.set_variants(p, deadly);
+        }
+        assert!(bug_plus_one(&bitmap).is_none());
+
+        bitmap.set_variants(point(0, 2), deadly | digit::SEVEN);
+        let bug = bug_plus_one(&bitmap).unwrap();
+        assert_eq!(bug.kind, UniquenessKind::BugPlusOne);
+        assert_eq!(
+            bug.placements,
+            vec![Candidate { point: point(0, 2), digit: digit::SEVEN }]
+        );
+
+        // a 2/5 cell in row 9 holds its 5 alone in the unit, so this is no grave
+        bitmap.set_variants(point(8, 8), pair(digit::TWO, digit::FIVE));
+        assert!(bug_plus_one(&bitmap).is_none());
+    }
+
+    #[test]
+    fn test_rectangle_in_puzzle() {
+        // .23.6........7.........98..1...3.4....7..4......8267.....6....945......28.25...4.
+        // once singles, intersections, subsets, fish, wings, coloring and chains are used up
+        let bitmap = pencilmarks("
+            579 2    3   14   6   8   19  1579  1457
+            569 189  89  134  7   135 2   1569  1456
+            567 147  45  2    15  9   8   13567 1367
+            1   689  689 79   3   57  4   2     568
+            2   3689 7   19   159 4   369 3569  3568
+            359 349  45  8    2   6   7   139   13
+            37  37   1   6    4   2   5   8     9
+            4   5    69  1379 8   137 136 1367  2
+            8   69   2   5    19  137 136 4     1367
+        ");
+        let solution =
+            "523168974918473256674259813196735428287914635345826791731642589459387162862591347";
+
+        let found = unique_rectangles(&bitmap);
+        assert_eq!(found[0].kind, UniquenessKind::HiddenRectangle);
+        assert_eq!(found[0].points, vec![point(0, 3), point(0, 8), point(1, 3), point(1, 8)]);
+        assert_eq!(found[0].digits, pair(digit::ONE, digit::FOUR));
+        assert_eq!(found[0].eliminations, vec![
+            Candidate { point: point(1, 8), digit: digit::ONE },
+        ]);
+        for rectangle in &found {
+            assert_sound(solution, &rectangle.eliminations);
+        }
+    }
+}
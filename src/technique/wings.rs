@@ -0,0 +1,296 @@
+use crate::bitmap::Bitmap;
+use crate::digit::Digit;
+use crate::grid;
+use crate::grid::GridPoint;
+use crate::technique::{
+    candidates_in, combinations, common_peer_eliminations, digit_points, list, outcome, Candidate,
+    Step, Technique,
+};
+use crate::variants;
+use crate::variants::Variants;
+use std::convert::TryInto;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum WingKind {
+    XY,
+    XYZ,
+    W,
+}
+
+/// A pattern of bivalue cells forcing `digit` into one of its wing cells, so
+/// cells seeing all of them lose it. `points` lists the pivot (or the strong
+/// link for a W-Wing) followed by the wing cells.
+#[derive(Clone, Debug)]
+pub struct Wing {
+    pub kind: WingKind,
+    pub points: Vec<GridPoint>,
+    pub digit: Digit,
+    pub eliminations: Vec<Candidate>,
+}
+
+impl Wing {
+    pub fn name(&self) -> &'static str {
+        match self.kind {
+            WingKind::XY => "XY-Wing",
+            WingKind::XYZ => "XYZ-Wing",
+            WingKind::W => "W-Wing",
+        }
+    }
+
+    pub fn step(&self, bitmap: &Bitmap) -> Step {
+        Step {
+            name: self.name(),
+            placements: Vec::new(),
+            eliminations: self.eliminations.clone(),
+            units: Vec::new(),
+            cells: self.points.clone(),
+            candidates: candidates_in(bitmap, &self.points, variants::ANY),
+            explanation: self.explanation(),
+        }
+    }
+
+    fn explanation(&self) -> String {
+        let pattern = match self.kind {
+            WingKind::W => format!(
+                "{} and {} are joined by the strong link {}-{}",
+                self.points[2], self.points[3], self.points[0], self.points[1]
+            ),
+            _ => format!(
+                "pivot {} sees the wings {}",
+                self.points[0],
+                list(self.points[1..].iter())
+            ),
+        };
+        format!(
+            "{}, so one of them is {} and {}",
+            pattern,
+            char::from(self.digit),
+            outcome(&[], &self.eliminations)
+        )
+    }
+}
+
+pub(crate) fn bivalue_points(bitmap: &Bitmap) -> Vec<GridPoint> {
+    grid::POINTS
+        .into_iter()
+        .filter(|&p| bitmap.get_variants(p).count() == 2)
+        .collect()
+}
+
+/// Pivot `xy` seeing pincers `xz` and `yz`: whichever digit the pivot takes,
+/// one pincer becomes `z`.
+pub fn xy_wings(bitmap: &Bitmap) -> Vec<Wing> {
+    pivot_wings(bitmap, WingKind::XY, 2, |pivot, a, b| {
+        (a | b | pivot).count() == 3 && a != b && a != pivot && b != pivot
+    })
+}
+
+/// Pivot `xyz` seeing pincers `xz` and `yz`. Cells losing `z` must also see
+/// the pivot.
+pub fn xyz_wings(bitmap: &Bitmap) -> Vec<Wing> {
+    pivot_wings(bitmap, WingKind::XYZ, 3, |pivot, a, b| {
+        a | pivot == pivot && b | pivot == pivot && a != b
+    })
+}
+
+fn pivot_wings(
+    bitmap: &Bitmap,
+    kind: WingKind,
+    pivot_size: u32,
+    matches: impl Fn(Variants, Variants, Variants) -> bool,
+) -> Vec<Wing> {
+    let bivalue = bivalue_points(bitmap);
+    let mut result = Vec::new();
+    for pivot in grid::POINTS {
+        let xy = bitmap.get_variants(pivot);
+        if xy.count() != pivot_size {
+            continue;
+        }
+        let pincers: Vec<GridPoint> = bivalue.iter().copied().filter(|p| p.sees(&pivot)).collect();
+        for picked in combinations(pincers.len(), 2) {
+            let (a, b) = (pincers[picked[0]], pincers[picked[1]]);
+            let (xz, yz) = (bitmap.get_variants(a), bitmap.get_variants(b));
+            if !matches(xy, xz, yz) {
+                continue;
+            }
+            let digit: Digit = match (xz & yz).try_into() {
+                Ok(d) => d,
+                Err(_) => continue,
+            };
+            let points = vec![pivot, a, b];
+            let seen = if kind == WingKind::XYZ { &points[..] } else { &points[1..] };
+            let eliminations = common_peer_eliminations(bitmap, digit, seen);
+            if !eliminations.is_empty() {
+                result.push(Wing {
+                    kind,
+                    points,
+                    digit,
+                    eliminations,
+                });
+            }
+        }
+    }
+    result
+}
+
+/// Two equal bivalue cells `xz` joined by a strong link on `x`: one of them
+/// must be `z`.
+pub fn w_wings(bitmap: &Bitmap) -> Vec<Wing> {
+    let bivalue = bivalue_points(bitmap);
+    let mut result = Vec::new();
+    for picked in combinations(bivalue.len(), 2) {
+        let (p, q) = (bivalue[picked[0]], bivalue[picked[1]]);
+        let pair = bitmap.get_variants(p);
+        if pair != bitmap.get_variants(q) || p.sees(&q) {
+            continue;
+        }
+        for x in pair {
+            let digit: Digit = (pair ^ x).try_into().unwrap();
+            for unit in grid::units() {
+                let link = digit_points(bitmap, unit, x);
+                if link.len() != 2 || link.contains(&p) || link.contains(&q) {
+                    continue;
+                }
+                let (a, b) = (link[0], link[1]);
+                if !(a.sees(&p) && b.sees(&q) || a.sees(&q) && b.sees(&p)) {
+                    continue;
+                }
+                let eliminations = common_peer_eliminations(bitmap, digit, &[p, q]);
+                if !eliminations.is_empty() {
+                    result.push(Wing {
+                        kind: WingKind::W,
+                        points: vec![a, b, p, q],
+                        digit,
+                        eliminations,
+                    });
+                }
+            }
+        }
+    }
+    result
+}
+
+pub struct XYWing;
+
+impl Technique for XYWing {
+    fn name(&self) -> &'static str {
+        "XY-Wing"
+    }
+
+    fn find(&self, bitmap: &Bitmap) -> Option<Step> {
+        xy_wings(bitmap).first().map(|w| w.step(bitmap))
+    }
+}
+
+pub struct XYZWing;
+
+impl Technique for XYZWing {
+    fn name(&self) -> &'static str {
+        "XYZ-Wing"
+    }
+
+    fn find(&self, bitmap: &Bitmap) -> Option<Step> {
+        xyz_wings(bitmap).first().map(|w| w.step(bitmap))
+    }
+}
+
+pub struct WWing;
+
+impl Technique for WWing {
+    fn name(&self) -> &'static str {
+        "W-Wing"
+    }
+
+    fn find(&self, bitmap: &Bitmap) -> Option<Step> {
+        w_wings(bitmap).first().map(|w| w.step(bitmap))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bitmap::Bitmap;
+    use crate::digit;
+    use crate::grid::{GridPoint, GridRow, GridUnit};
+    use crate::technique::fixtures::{assert_sound, pencilmarks, point, variants};
+    use crate::technique::wings::{w_wings, xy_wings, xyz_wings};
+    use crate::technique::Candidate;
+
+    #[test]
+    fn test_xy_wing() {
+        let mut bitmap = Bitmap::new();
+        bitmap.set_variants(point(0, 0), variants(&[digit::ONE, digit::TWO]));
+        bitmap.set_variants(point(0, 5), variants(&[digit::ONE, digit::THREE]));
+        bitmap.set_variants(point(4, 0), variants(&[digit::TWO, digit::THREE]));
+
+        let found = xy_wings(&bitmap);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].points[0], point(0, 0));
+        assert_eq!(found[0].digit, digit::THREE);
+        let eliminated: Vec<GridPoint> = found[0].eliminations.iter().map(|c| c.point).collect();
+        assert_eq!(eliminated, vec![point(4, 5)]);
+        assert!(xyz_wings(&bitmap).is_empty());
+    }
+
+    #[test]
+    fn test_xyz_wing() {
+        let mut bitmap = Bitmap::new();
+        bitmap.set_variants(point(0, 0), variants(&[digit::ONE, digit::TWO, digit::THREE]));
+        bitmap.set_variants(point(0, 4), variants(&[digit::ONE, digit::THREE]));
+        bitmap.set_variants(point(1, 1), variants(&[digit::TWO, digit::THREE]));
+
+        let found = xyz_wings(&bitmap);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name(), "XYZ-Wing");
+        let eliminated: Vec<GridPoint> = found[0].eliminations.iter().map(|c| c.point).collect();
+        assert_eq!(eliminated, vec![point(0, 1), point(0, 2)]);
+        assert!(xy_wings(&bitmap).is_empty());
+    }
+
+    #[test]
+    fn test_w_wing() {
+        let mut bitmap = Bitmap::new();
+        let pair = variants(&[digit::FOUR, digit::SEVEN]);
+        bitmap.set_variants(point(0, 0), pair);
+        bitmap.set_variants(point(8, 8), pair);
+        for p in GridUnit::Row(GridRow(4)).points() {
+            if p != point(4, 0) && p != point(4, 8) {
+                bitmap.remove_variant(p, digit::FOUR);
+            }
+        }
+
+        let found = w_wings(&bitmap);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].digit, digit::SEVEN);
+        let eliminated: Vec<GridPoint> = found[0].eliminations.iter().map(|c| c.point).collect();
+        assert_eq!(eliminated, vec![point(0, 8), point(8, 0)]);
+    }
+
+    #[test]
+    fn test_wings_in_puzzle() {
+        // 62.......49..2...8...9.8...8....61........94.3....48.2.8.5..4...5.3.7........1.5.
+        // once singles, intersections, subsets and fish are used up
+        let bitmap = pencilmarks("
+            6   2  8   4 17 35 357 9  13
+            4   9  17  6 2  35 35  17 8
+            157 13 135 9 17 8  6   2  4
+            8   4  2   7 9  6  1   3  5
+            15  16 156 8 3  2  9   4  7
+            3   7  9   1 5  4  8   6  2
+            2   8  37  5 6  9  4   17 13
+            19  5  16  3 4  7  2   8  69
+            79  36 4   2 8  1  37  5  69
+        ");
+        let solution =
+            "628415793497623518135978624842796135516832947379154862283569471951347286764281359";
+
+        let found = xy_wings(&bitmap);
+        assert_eq!(found[0].points, vec![point(1, 2), point(2, 1), point(6, 2)]);
+        assert_eq!(found[0].eliminations, vec![
+            Candidate { point: point(2, 2), digit: digit::THREE },
+            Candidate { point: point(8, 1), digit: digit::THREE },
+        ]);
+        for wing in xy_wings(&bitmap).iter().chain(&xyz_wings(&bitmap)).chain(&w_wings(&bitmap)) {
+            assert_sound(solution, &wing.eliminations);
+        }
+    }
+}
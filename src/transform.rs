@@ -0,0 +1,349 @@
+use crate::bitmap::Bitmap;
+use crate::digit::Digit;
+use crate::generator::Random;
+use crate::grid;
+use crate::grid::{GridColumn, GridPoint, GridRow};
+use crate::solution::for_each_point;
+use crate::variants::Variants;
+use std::convert::TryFrom;
+
+/// A relabelling of the grid that keeps every valid grid valid: an optional
+/// transposition, then rows and columns moved within the band and stack
+/// structure, and digits swapped for each other.
+///
+/// Permutations are given as the new order, so `[2, 0, 1]` moves the third
+/// band, stack or line to the front. Lines are counted from 0 within their
+/// band or stack, and digits are given as `Digit`s in the same way.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transform {
+    transpose: bool,
+    /// Old row to new row, after the transposition.
+    rows: [usize; grid::HEIGHT],
+    /// Old column to new column, after the transposition.
+    columns: [usize; grid::WIDTH],
+    /// Old digit to new digit.
+    digits: [usize; grid::WIDTH],
+}
+
+const IDENTITY: [usize; 9] = [0, 1, 2, 3, 4, 5, 6, 7, 8];
+
+impl Transform {
+    pub fn identity() -> Transform {
+        Transform {
+            transpose: false,
+            rows: IDENTITY,
+            columns: IDENTITY,
+            digits: IDENTITY,
+        }
+    }
+
+    /// Relabels digits in their new order: `order[0]` becomes 1, `order[1]`
+    /// becomes 2 and so on.
+    pub fn permute_digits(order: [Digit; 9]) -> Result<Transform, &'static str> {
+        let mut old = [0; 9];
+        for (i, &d) in order.iter().enumerate() {
+            old[i] = usize::from(d);
+        }
+        check_permutation(&old)?;
+        Ok(Transform {
+            digits: invert(&old),
+            ..Transform::identity()
+        })
+    }
+
+    pub fn permute_rows_in_band(band: usize, order: [usize; 3]) -> Result<Transform, &'static str> {
+        Ok(Transform {
+            rows: within(band, order)?,
+            ..Transform::identity()
+        })
+    }
+
+    pub fn permute_columns_in_stack(
+        stack: usize,
+        order: [usize; 3],
+    ) -> Result<Transform, &'static str> {
+        Ok(Transform {
+            columns: within(stack, order)?,
+            ..Transform::identity()
+        })
+    }
+
+    pub fn permute_bands(order: [usize; 3]) -> Result<Transform, &'static str> {
+        Ok(Transform {
+            rows: blocks(order)?,
+            ..Transform::identity()
+        })
+    }
+
+    pub fn permute_stacks(order: [usize; 3]) -> Result<Transform, &'static str> {
+        Ok(Transform {
+            columns: blocks(order)?,
+            ..Transform::identity()
+        })
+    }
+
+    /// Reflects the grid in its main diagonal.
+    pub fn transpose() -> Transform {
+        Transform {
+            transpose: true,
+            ..Transform::identity()
+        }
+    }
+
+    /// Turns the grid a quarter clockwise, like `GridPoint::rotate_90`.
+    pub fn rotate_90() -> Transform {
+        let mut columns = [0; 9];
+        for (c, column) in columns.iter_mut().enumerate() {
+            *column = grid::WIDTH - 1 - c;
+        }
+        Transform {
+            transpose: true,
+            columns,
+            ..Transform::identity()
+        }
+    }
+
+    /// A transform picked uniformly from the whole group.
+    pub fn random(random: &mut Random) -> Transform {
+        let rows = random_lines(random);
+        let columns = random_lines(random);
+        let mut digits = IDENTITY;
+        random.shuffle(&mut digits);
+        Transform {
+            transpose: random.below(2) == 1,
+            rows,
+            columns,
+            digits,
+        }
+    }
+
+    /// `self` followed by `other`.
+    pub fn then(&self, other: &Transform) -> Transform {
+        // transposing after moving rows and columns is the same as moving
+        // the columns as rows, and the rows as columns, after transposing
+        let (rows, columns) = if other.transpose {
+            (self.columns, self.rows)
+        } else {
+            (self.rows, self.columns)
+        };
+        Transform {
+            transpose: self.transpose != other.transpose,
+            rows: compose(&rows, &other.rows),
+            columns: compose(&columns, &other.columns),
+            digits: compose(&self.digits, &other.digits),
+        }
+    }
+
+    pub fn inverse(&self) -> Transform {
+        let (rows, columns) = if self.transpose {
+            (invert(&self.columns), invert(&self.rows))
+        } else {
+            (invert(&self.rows), invert(&self.columns))
+        };
+        Transform {
+            transpose: self.transpose,
+            rows,
+            columns,
+            digits: invert(&self.digits),
+        }
+    }
+
+    pub fn apply_point(&self, p: GridPoint) -> GridPoint {
+        let (y, x) = if self.transpose {
+            (p.column().0, p.row().0)
+        } else {
+            (p.row().0, p.column().0)
+        };
+        GridPoint {
+            x: GridColumn(self.columns[x]),
+            y: GridRow(self.rows[y]),
+        }
+    }
+
+    pub fn apply_digit(&self, d: Digit) -> Digit {
+        Digit::try_from(self.digits[usize::from(d)] as u8).unwrap()
+    }
+
+    pub fn apply_variants(&self, variants: Variants) -> Variants {
+        variants
+            .into_iter()
+            .fold(Variants::new(), |acc, d| acc | self.apply_digit(d))
+    }
+
+    pub fn apply_board(&self, board: &Vec<Vec<char>>) -> Vec<Vec<char>> {
+        let mut result = vec![vec!['.'; grid::WIDTH]; grid::HEIGHT];
+        for_each_point(board, |p, &ch| {
+            let q = self.apply_point(p);
+            result[q.row().0][q.column().0] = match Digit::try_from(ch) {
+                Ok(d) => self.apply_digit(d).into(),
+                Err(_) => ch,
+            };
+        });
+        result
+    }
+
+    pub fn apply_bitmap(&self, bitmap: &Bitmap) -> Bitmap {
+        let mut result = Bitmap::new();
+        for p in grid::POINTS {
+            result.set_variants(
+                self.apply_point(p),
+                self.apply_variants(bitmap.get_variants(p)),
+            );
+        }
+        result
+    }
+}
+
+fn check_permutation(order: &[usize]) -> Result<(), &'static str> {
+    let mut seen = vec![false; order.len()];
+    for &i in order {
+        if i >= order.len() || seen[i] {
+            return Err("invalid permutation");
+        }
+        seen[i] = true;
+    }
+    Ok(())
+}
+
+/// Shuffles bands, or stacks, and the lines within each of them.
+fn random_lines(random: &mut Random) -> [usize; 9] {
+    let mut outer = [0, 1, 2];
+    random.shuffle(&mut outer);
+    let mut map = [0; 9];
+    for (new_outer, &old_outer) in outer.iter().enumerate() {
+        let mut inner = [0, 1, 2];
+        random.shuffle(&mut inner);
+        for (new_inner, &old_inner) in inner.iter().enumerate() {
+            map[old_outer * 3 + old_inner] = new_outer * 3 + new_inner;
+        }
+    }
+    map
+}
+
+/// Moves lines within one band or stack, given in their new order.
+fn within(outer: usize, order: [usize; 3]) -> Result<[usize; 9], &'static str> {
+    check_permutation(&order)?;
+    if outer >= grid::NUM_BLOCKS {
+        return Err("invalid band or stack");
+    }
+    let mut map = IDENTITY;
+    for (new, &old) in order.iter().enumerate() {
+        map[outer * 3 + old] = outer * 3 + new;
+    }
+    Ok(map)
+}
+
+/// Moves whole bands or stacks, given in their new order.
+fn blocks(order: [usize; 3]) -> Result<[usize; 9], &'static str> {
+    check_permutation(&order)?;
+    let mut map = IDENTITY;
+    for (new, &old) in order.iter().enumerate() {
+        for i in 0..grid::BLOCK_SIZE {
+            map[old * 3 + i] = new * 3 + i;
+        }
+    }
+    Ok(map)
+}
+
+/// `first` followed by `second`.
+fn compose(first: &[usize; 9], second: &[usize; 9]) -> [usize; 9] {
+    let mut map = [0; 9];
+    for (i, m) in map.iter_mut().enumerate() {
+        *m = second[first[i]];
+    }
+    map
+}
+
+fn invert(map: &[usize; 9]) -> [usize; 9] {
+    let mut inverse = [0; 9];
+    for (i, &m) in map.iter().enumerate() {
+        inverse[m] = i;
+    }
+    inverse
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::digit;
+    use crate::digit::Digit;
+    use crate::generator::Random;
+    use crate::grid;
+    use crate::grid::point;
+    use crate::solution::{parse, to_bitmap, Solution};
+    use crate::transform::Transform;
+    use std::convert::TryFrom;
+
+    const PUZZLE: &str =
+        ".....1..74......39.21...4..94.......1..9...85.5..3....8...4....6....8..2....25.6.";
+    const SOLUTION: &str =
+        "539461827486257139721389456948512673163974285257836914872643591615798342394125768";
+
+    #[test]
+    fn test_generators() {
+        let rows = Transform::permute_rows_in_band(1, [2, 0, 1]).unwrap();
+        assert_eq!(rows.apply_point(point(5, 0)), point(3, 0));
+        let stacks = Transform::permute_stacks([1, 2, 0]).unwrap();
+        assert_eq!(stacks.apply_point(point(0, 4)), point(0, 1));
+        assert_eq!(Transform::transpose().apply_point(point(1, 7)), point(7, 1));
+        assert_eq!(
+            Transform::rotate_90().apply_point(point(1, 7)),
+            point(1, 7).rotate_90()
+        );
+
+        let mut order = [digit::ONE; 9];
+        for (i, d) in order.iter_mut().enumerate() {
+            *d = Digit::try_from(i as u8).unwrap();
+        }
+        order[..3].rotate_left(1);
+        let cycle = Transform::permute_digits(order).unwrap();
+        assert_eq!(cycle.apply_digit(digit::TWO), digit::ONE);
+        assert_eq!(cycle.apply_digit(digit::THREE), digit::TWO);
+        assert_eq!(cycle.apply_digit(digit::ONE), digit::THREE);
+        assert_eq!(cycle.apply_digit(digit::FOUR), digit::FOUR);
+        order[0] = digit::THREE;
+        assert!(Transform::permute_digits(order).is_err());
+
+        assert!(Transform::permute_bands([0, 0, 1]).is_err());
+        assert!(Transform::permute_columns_in_stack(3, [0, 1, 2]).is_err());
+    }
+
+    #[test]
+    fn test_compose_and_invert() {
+        let mut random = Random::new(1);
+        let quarter = Transform::rotate_90();
+        let full = quarter.then(&quarter).then(&quarter).then(&quarter);
+        assert_eq!(full, Transform::identity());
+
+        let board = parse(SOLUTION);
+        for _ in 0..20 {
+            let a = Transform::random(&mut random);
+            let b = Transform::random(&mut random);
+            assert_eq!(a.then(&a.inverse()), Transform::identity());
+            assert_eq!(a.inverse().then(&a), Transform::identity());
+            assert_eq!(
+                a.then(&b).apply_board(&board),
+                b.apply_board(&a.apply_board(&board))
+            );
+        }
+    }
+
+    #[test]
+    fn test_preserves_solutions() {
+        let mut random = Random::new(2);
+        for _ in 0..5 {
+            let t = Transform::random(&mut random);
+            let mut board = t.apply_board(&parse(PUZZLE));
+            Solution::solve_sudoku(&mut board).unwrap();
+            assert_eq!(board, t.apply_board(&parse(SOLUTION)));
+
+            let mut bitmap = to_bitmap(&parse(PUZZLE));
+            bitmap.propagate().unwrap();
+            let moved = t.apply_bitmap(&bitmap);
+            let mut expected = to_bitmap(&t.apply_board(&parse(PUZZLE)));
+            expected.propagate().unwrap();
+            for p in grid::POINTS {
+                assert_eq!(moved.get_variants(p), expected.get_variants(p));
+            }
+        }
+    }
+}
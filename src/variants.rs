@@ -24,17 +24,61 @@ impl cmp::PartialEq for Variants {
 }
 
 impl Variants {
-    fn new() -> Variants {
+    pub fn new() -> Variants {
         Variants(0)
     }
-    fn count(self) -> u32 {
+    pub fn count(self) -> u32 {
         self.0.count_ones()
     }
-    fn has_digit(self, d: Digit) -> bool {
+    pub fn has_digit(self, d: Digit) -> bool {
         self.0 & Variants::from(d).0 != 0
     }
 }
 
+impl Default for Variants {
+    fn default() -> Variants {
+        Variants::new()
+    }
+}
+
+impl IntoIterator for Variants {
+    type Item = Digit;
+    type IntoIter = VariantsIterator;
+    fn into_iter(self) -> Self::IntoIter {
+        VariantsIterator { rest: self.0 }
+    }
+}
+
+pub struct VariantsIterator {
+    rest: u16,
+}
+
+impl Iterator for VariantsIterator {
+    type Item = Digit;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest == 0 {
+            None
+        } else {
+            let digit = Digit::try_from(self.rest.trailing_zeros()).unwrap();
+            self.rest &= self.rest - 1;
+            Some(digit)
+        }
+    }
+}
+
+impl DoubleEndedIterator for VariantsIterator {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.rest == 0 {
+            None
+        } else {
+            let highest = u16::BITS - 1 - self.rest.leading_zeros();
+            self.rest ^= 1 << highest;
+            Some(Digit::try_from(highest).unwrap())
+        }
+    }
+}
+
 impl From<Digit> for Variants {
     fn from(d: Digit) -> Self {
         Variants(0x1 << u8::from(d))
@@ -93,6 +137,22 @@ impl ops::BitOrAssign<Digit> for Variants {
     }
 }
 
+impl ops::BitAnd<Variants> for Variants {
+    type Output = Variants;
+
+    fn bitand(self, rhs: Variants) -> Self::Output {
+        Variants(self.0 & rhs.0)
+    }
+}
+
+impl ops::Not for Variants {
+    type Output = Variants;
+
+    fn not(self) -> Self::Output {
+        Variants(!self.0 & MASK as u16)
+    }
+}
+
 impl ops::BitXor<Variants> for Variants {
     type Output = Variants;
 
@@ -138,4 +198,13 @@ mod tests {
         v |= digit::ONE; // already there
         assert_eq!(v.count(), 2);
     }
+
+    #[test]
+    fn test_iter() {
+        let v = Variants::new() | digit::NINE | digit::TWO | digit::FIVE;
+        let digits: Vec<Digit> = v.into_iter().collect();
+        assert_eq!(digits, vec![digit::TWO, digit::FIVE, digit::NINE]);
+        let digits: Vec<Digit> = v.into_iter().rev().collect();
+        assert_eq!(digits, vec![digit::NINE, digit::FIVE, digit::TWO]);
+    }
 }